@@ -1,9 +1,10 @@
-use signal_msg::{self, SignalReceiver, SignalSender};
+use signal_msg::Signals;
+use std::error::Error;
 
-fn main() {
-    let (signal_sender, signal_receiver) = signal_msg::new();
-    signal_sender.prepare_signals();
+fn main() -> Result<(), Box<dyn Error>> {
+    let signals = Signals::new()?;
     println!("Waiting for a signal...");
-    let sig = signal_receiver.listen();
-    println!("Got signal: {:?}", sig.unwrap());
+    let sig = signals.recv()?;
+    println!("Got signal: {}", sig);
+    Ok(())
 }
@@ -0,0 +1,16 @@
+use signal_msg::Signals;
+
+fn main() {
+    let signals = Signals::new().expect("couldn't install signal handlers");
+    let receiver = signals.subscribe();
+    println!(
+        "Waiting for signals (try SIGUSR1, SIGWINCH or SIGCONT with `kill -USR1 {}`, or Ctrl-C to quit)...",
+        std::process::id()
+    );
+    for sig in receiver {
+        println!("Got signal: {}", sig);
+        if sig.is_terminating() {
+            break;
+        }
+    }
+}
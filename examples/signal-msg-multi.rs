@@ -0,0 +1,30 @@
+use signal_msg::Signals;
+use std::thread;
+
+fn main() {
+    let signals = Signals::new().expect("couldn't install signal handlers");
+    let r1 = signals.subscribe();
+    let r2 = signals.subscribe();
+    println!(
+        "Waiting for signals in two threads (pid {}, Ctrl-C to quit)...",
+        std::process::id()
+    );
+    let t1 = thread::spawn(move || {
+        for sig in r1 {
+            println!("[thread 1] Got signal: {}", sig);
+            if sig.is_terminating() {
+                break;
+            }
+        }
+    });
+    let t2 = thread::spawn(move || {
+        for sig in r2 {
+            println!("[thread 2] Got signal: {}", sig);
+            if sig.is_terminating() {
+                break;
+            }
+        }
+    });
+    t1.join().unwrap();
+    t2.join().unwrap();
+}
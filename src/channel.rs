@@ -0,0 +1,488 @@
+// The channel behind every `Signals` subscription. It's a plain queue under a
+// mutex rather than `std::sync::mpsc`, because the fan-out needs to look at
+// what a subscriber hasn't read yet (e.g. to coalesce repeated signals),
+// which mpsc doesn't allow.
+//
+// A channel can have a capacity. What sending does when it's full depends
+// on the channel's `OverflowPolicy`: drop the oldest value that isn't
+// protected (terminating signals, for subscriptions), drop the new value,
+// or wait for the receiver to make room. Protected values are never dropped
+// or kept waiting, even if that means going over capacity. When everything
+// queued is protected, dropping the oldest falls back to dropping the new
+// value.
+//
+// Besides values, the queue carries the messages that control the
+// receiving end (see `Dispatch`), so there is exactly one thing a receiver
+// waits on and everything it learns comes in the order it happened.
+use super::SignalError;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant};
+
+/// What a bounded subscription (see `SignalsBuilder::buffer_capacity`) does
+/// with a signal that arrives while its queue is full. Terminating signals
+/// (including those an instance treats as such, see
+/// `Signals::treat_as_terminating`) are never dropped or kept waiting,
+/// whatever the policy: they're queued even if that means going over
+/// capacity.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OverflowPolicy {
+    /// Drops the oldest signal in the queue that isn't terminating to make
+    /// room, so the subscriber sees the most recent ones. This is the
+    /// default.
+    #[default]
+    DropOldest,
+    /// Drops the signal that just arrived, so the subscriber sees the
+    /// earliest ones.
+    DropNewest,
+    /// Waits for the subscriber to make room, so nothing is dropped. The
+    /// waiting happens on the instance's dispatch thread, so every other
+    /// subscriber of the instance waits too. Closing the instance stops the
+    /// waiting; the signal is dropped then.
+    Block,
+}
+
+struct Chan<T> {
+    state: Mutex<State<T>>,
+    ready: Condvar,
+    // Signalled when a `Block` sender may have room again.
+    space: Condvar,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    protected: Box<dyn Fn(&T) -> bool + Send + Sync>,
+}
+
+struct State<T> {
+    queue: VecDeque<Dispatch<T>>,
+    sender_alive: bool,
+    receiver_alive: bool,
+    // Set by `Stop::stop`, after which sends never wait for room.
+    stopped: bool,
+    // Called after each value is queued, for `Signals`' `as_raw_fd`.
+    on_send: Option<Box<dyn Fn() + Send>>,
+}
+
+// What a channel's queue holds.
+enum Dispatch<T> {
+    // A value to hand to the receiver.
+    Signal(T),
+    // Nothing to hand over, only a nudge to look at the channel again, e.g.
+    // because the sender has gone.
+    Wakeup,
+    // The receiver was cancelled. This stays at the front of the queue so
+    // that every later call sees it too.
+    Cancel,
+}
+
+impl<T> State<T> {
+    // The next thing for the receiver: a value, or `ChannelClosed` once
+    // cancelled. Wakeups are skipped, as they carry nothing to return.
+    // `None` means there's nothing queued.
+    fn take(&mut self) -> Option<Result<T, SignalError>> {
+        loop {
+            match self.queue.pop_front()? {
+                Dispatch::Signal(v) => return Some(Ok(v)),
+                Dispatch::Wakeup => continue,
+                Dispatch::Cancel => {
+                    self.queue.push_front(Dispatch::Cancel);
+                    return Some(Err(SignalError::ChannelClosed));
+                }
+            }
+        }
+    }
+
+    fn queued(&self) -> usize {
+        self.queue
+            .iter()
+            .filter(|d| matches!(d, Dispatch::Signal(_)))
+            .count()
+    }
+}
+
+// Lets whoever tears down the sending side stop a send that's waiting for
+// room, without having to know what the channel carries.
+pub(crate) trait Stop: Send + Sync {
+    fn stop(&self);
+}
+
+impl<T: Send> Stop for Chan<T> {
+    fn stop(&self) {
+        self.state.lock().unwrap().stopped = true;
+        self.space.notify_all();
+    }
+}
+
+pub(crate) fn channel<T, F>(
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    protected: F,
+) -> (Sender<T>, Receiver<T>)
+where
+    F: Fn(&T) -> bool + Send + Sync + 'static,
+{
+    let chan = Arc::new(Chan {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            sender_alive: true,
+            receiver_alive: true,
+            stopped: false,
+            on_send: None,
+        }),
+        ready: Condvar::new(),
+        space: Condvar::new(),
+        capacity,
+        policy,
+        protected: Box::new(protected),
+    });
+    (
+        Sender {
+            chan: Arc::clone(&chan),
+        },
+        Receiver {
+            chan,
+            on_drop: None,
+            resubscribe: None,
+        },
+    )
+}
+
+// A receiver whose sender is already gone.
+pub(crate) fn closed<T>() -> Receiver<T> {
+    channel(None, OverflowPolicy::default(), |_| true).1
+}
+
+pub(crate) struct Sender<T> {
+    chan: Arc<Chan<T>>,
+}
+
+impl<T> Sender<T> {
+    // Fails if the receiver is gone, like `mpsc::Sender::send`. Otherwise
+    // returns whatever was dropped to stay within capacity: the oldest
+    // unprotected value, or `value` itself.
+    pub(crate) fn send(&self, value: T) -> Result<Option<T>, T> {
+        let mut state = self.chan.state.lock().unwrap();
+        if !state.receiver_alive {
+            return Err(value);
+        }
+        let mut dropped = None;
+        if let Some(capacity) = self.chan.capacity {
+            let protected = &self.chan.protected;
+            if self.chan.policy == OverflowPolicy::Block && !protected(&value) {
+                while state.receiver_alive && !state.stopped && state.queued() >= capacity {
+                    state = self.chan.space.wait(state).unwrap();
+                }
+                if !state.receiver_alive {
+                    return Err(value);
+                }
+            }
+            if state.queued() >= capacity {
+                let droppable = |d: &Dispatch<T>| matches!(d, Dispatch::Signal(v) if !protected(v));
+                let oldest = match self.chan.policy {
+                    OverflowPolicy::DropOldest => state.queue.iter().position(droppable),
+                    // Only stopped `Block` senders get here.
+                    OverflowPolicy::DropNewest | OverflowPolicy::Block => None,
+                };
+                match oldest {
+                    Some(pos) => {
+                        if let Some(Dispatch::Signal(old)) = state.queue.remove(pos) {
+                            dropped = Some(old);
+                        }
+                    }
+                    None if !protected(&value) => return Ok(Some(value)),
+                    None => {}
+                }
+            }
+        }
+        state.queue.push_back(Dispatch::Signal(value));
+        if let Some(f) = &state.on_send {
+            f();
+        }
+        self.chan.ready.notify_one();
+        Ok(dropped)
+    }
+
+    // Like `send`, but drops `value` if an equal one is still unread.
+    pub(crate) fn send_coalesced(&self, value: T) -> Result<Option<T>, T>
+    where
+        T: PartialEq,
+    {
+        {
+            let state = self.chan.state.lock().unwrap();
+            let unread = state
+                .queue
+                .iter()
+                .any(|d| matches!(d, Dispatch::Signal(v) if *v == value));
+            if state.receiver_alive && unread {
+                return Ok(Some(value));
+            }
+        }
+        self.send(value)
+    }
+
+    // A handle for stopping this sender from waiting for room.
+    pub(crate) fn stopper(&self) -> Weak<dyn Stop>
+    where
+        T: Send + 'static,
+    {
+        let chan: Arc<dyn Stop> = self.chan.clone();
+        Arc::downgrade(&chan)
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.chan.state.lock().unwrap();
+        state.sender_alive = false;
+        state.queue.push_back(Dispatch::Wakeup);
+        self.chan.ready.notify_all();
+    }
+}
+
+type Resubscribe<T> = Arc<dyn Fn() -> Receiver<T> + Send + Sync>;
+
+pub struct Receiver<T> {
+    chan: Arc<Chan<T>>,
+    on_drop: Option<Box<dyn FnOnce() + Send>>,
+    resubscribe: Option<Resubscribe<T>>,
+}
+
+impl<T> Receiver<T> {
+    // Runs `f` when the receiver is dropped, which is how subscriptions take
+    // themselves out of their `Signals`' registry.
+    pub(crate) fn on_drop(&mut self, f: Box<dyn FnOnce() + Send>) {
+        self.on_drop = Some(f);
+    }
+
+    // Sets how `resubscribe` gets a new subscription like this one.
+    pub(crate) fn on_resubscribe(&mut self, f: Resubscribe<T>) {
+        self.resubscribe = Some(f);
+    }
+
+    /// Subscribes again to the `Signals` this receiver came from, with the
+    /// same kind of subscription and filter, so code holding only a
+    /// receiver can re-arm after tearing down, like
+    /// `tokio::sync::broadcast::Receiver::resubscribe`. The new receiver
+    /// only gets signals delivered after this call (plus the usual replay
+    /// of the latest terminating signal), so anything delivered between a
+    /// teardown and the resubscribe is missed. If the `Signals` is gone or
+    /// closed, the new receiver is closed too.
+    pub fn resubscribe(&self) -> Receiver<T> {
+        match &self.resubscribe {
+            Some(f) => f(),
+            None => closed(),
+        }
+    }
+
+    // Calls `f` every time a value is queued from now on, and right away if
+    // there is one already.
+    #[cfg(unix)]
+    pub(crate) fn on_send(&self, f: Box<dyn Fn() + Send>) {
+        let mut state = self.lock();
+        if state.queue.iter().any(|d| matches!(d, Dispatch::Signal(_))) {
+            f();
+        }
+        state.on_send = Some(f);
+    }
+
+    // A handle that closes this receiver from anywhere.
+    pub(crate) fn cancel_handle(&self) -> CancelHandle
+    where
+        T: Send + 'static,
+    {
+        let chan = Arc::downgrade(&self.chan);
+        CancelHandle {
+            cancel: Arc::new(move || cancel(&chan)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.chan.state.lock().unwrap()
+    }
+
+    // `State::take`, letting a sender waiting for room know there may be
+    // some.
+    fn take(&self, state: &mut State<T>) -> Option<Result<T, SignalError>> {
+        let next = state.take();
+        if next.is_some() {
+            self.chan.space.notify_one();
+        }
+        next
+    }
+
+    /// The number of values queued and not yet received. This is a snapshot
+    /// taken under the channel's lock: it is exact at that moment, but the
+    /// fan-out may queue more right after it returns, so treat it as a lower
+    /// bound.
+    pub fn pending(&self) -> usize {
+        self.lock().queued()
+    }
+
+    /// Takes everything queued at once, in order, without blocking: an
+    /// empty vector if nothing is, and also once the receiver is closed or
+    /// cancelled. The queue is emptied under a single lock, which is
+    /// cheaper than calling `try_recv` until it comes up empty when signals
+    /// arrive in bursts.
+    pub fn drain_available(&self) -> Vec<T> {
+        let mut state = self.lock();
+        let mut values = Vec::new();
+        while let Some(Ok(v)) = self.take(&mut state) {
+            values.push(v);
+        }
+        values
+    }
+
+    pub fn recv(&self) -> Result<T, SignalError> {
+        let mut state = self.lock();
+        loop {
+            if let Some(next) = self.take(&mut state) {
+                return next;
+            }
+            if !state.sender_alive {
+                return Err(SignalError::ChannelClosed);
+            }
+            state = self.chan.ready.wait(state).unwrap();
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<Option<T>, SignalError> {
+        let mut state = self.lock();
+        match self.take(&mut state) {
+            Some(next) => next.map(Some),
+            None if state.sender_alive => Ok(None),
+            None => Err(SignalError::ChannelClosed),
+        }
+    }
+
+    pub fn recv_timeout(&self, dur: Duration) -> Result<Option<T>, SignalError> {
+        let deadline = Instant::now() + dur;
+        let mut state = self.lock();
+        loop {
+            if let Some(next) = self.take(&mut state) {
+                return next.map(Some);
+            }
+            if !state.sender_alive {
+                return Err(SignalError::ChannelClosed);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            state = self
+                .chan
+                .ready
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+}
+
+// Wakes up anyone blocked on the receiver and has it act as if closed from
+// then on. Marking it dead also makes the fan-out drop its sender.
+fn cancel<T>(chan: &Weak<Chan<T>>) {
+    if let Some(chan) = chan.upgrade() {
+        let mut state = chan.state.lock().unwrap();
+        state.receiver_alive = false;
+        state.queue.clear();
+        state.queue.push_back(Dispatch::Cancel);
+        chan.ready.notify_all();
+        chan.space.notify_all();
+    }
+}
+
+/// Closes the receiver it came with (see `Signals::subscribe_cancellable`),
+/// from any thread. Clones cancel the same receiver.
+#[derive(Clone)]
+pub struct CancelHandle {
+    cancel: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl CancelHandle {
+    /// Makes the receiver act as if its `Signals` had gone away: a blocked
+    /// `recv` (or iterator) returns right away with `ChannelClosed` (or
+    /// `None`), and so does every later call. Anything still queued is
+    /// dropped. Cancelling twice, or after the receiver is gone, does
+    /// nothing.
+    pub fn cancel(&self) {
+        (self.cancel)()
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.recv().ok()
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        // A fan-out waiting for room holds the registry lock, so it has to
+        // be told the receiver is gone before `on_drop` takes that lock.
+        // The channel lock is let go of first: the fan-out takes the two in
+        // the other order.
+        {
+            let mut state = self.lock();
+            state.receiver_alive = false;
+            state.queue.clear();
+            self.chan.space.notify_all();
+        }
+        if let Some(f) = self.on_drop.take() {
+            f();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Signal, SignalReceiver};
+    use std::thread;
+
+    fn unbounded() -> (Sender<Signal>, Receiver<Signal>) {
+        channel(None, OverflowPolicy::default(), |_| false)
+    }
+
+    fn push(receiver: &Receiver<Signal>, dispatch: Dispatch<Signal>) {
+        receiver.lock().queue.push_back(dispatch);
+        receiver.chan.ready.notify_all();
+    }
+
+    #[test]
+    fn listen_returns_queued_signals() {
+        let (_sender, receiver) = unbounded();
+        push(&receiver, Dispatch::Signal(Signal::Usr1));
+        assert_eq!(receiver.listen(), Ok(Signal::Usr1));
+    }
+
+    #[test]
+    fn listen_sleeps_through_wakeups() {
+        let (sender, receiver) = unbounded();
+        push(&receiver, Dispatch::Wakeup);
+        assert_eq!(receiver.try_listen(), Ok(None));
+        push(&receiver, Dispatch::Wakeup);
+        let sending = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            sender.send(Signal::Usr2).unwrap();
+            sender
+        });
+        assert_eq!(receiver.listen(), Ok(Signal::Usr2));
+        drop(sending.join().unwrap());
+        // The sender going away wakes the receiver up for good.
+        assert_eq!(receiver.listen(), Err(SignalError::ChannelClosed));
+    }
+
+    #[test]
+    fn listen_fails_once_cancelled() {
+        let (_sender, receiver) = unbounded();
+        push(&receiver, Dispatch::Signal(Signal::Usr1));
+        receiver.cancel_handle().cancel();
+        assert_eq!(receiver.listen(), Err(SignalError::ChannelClosed));
+        assert_eq!(receiver.try_listen(), Err(SignalError::ChannelClosed));
+        assert_eq!(
+            receiver.listen_timeout(Duration::from_millis(1)),
+            Err(SignalError::ChannelClosed)
+        );
+    }
+}
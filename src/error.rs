@@ -0,0 +1,71 @@
+use crate::Signal;
+use alloc::string::{String, ToString};
+use core::{error, fmt};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SignalError {
+    UnsupportedSignal(i32),
+    UnsupportedSignalName(String),
+    /// The OS refused to install a handler for `signal`, with the error
+    /// code `errno` (on Windows, the `GetLastError` code).
+    HandlerInstallFailed {
+        signal: i32,
+        errno: i32,
+    },
+    ChannelClosed,
+    /// SIGKILL and SIGSTOP can't be caught, blocked or ignored.
+    Uncatchable(Signal),
+    /// A subscription can't hold less than one signal.
+    InvalidBufferCapacity(usize),
+    /// `set_ctrl_c_handler` was already called.
+    HandlerAlreadySet,
+    /// The OS refused the alternate signal stack asked for with
+    /// `SignalsBuilder::alt_stack`, with the error code `errno`.
+    AltStackFailed {
+        errno: i32,
+    },
+    /// The pipe behind `Signals::as_raw_fd`, or the one signals are passed
+    /// on through on UNIX, couldn't be created, with the error code `errno`
+    /// (e.g. because the process is out of file descriptors).
+    NotifierFailed {
+        errno: i32,
+    },
+}
+
+impl fmt::Display for SignalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignalError::UnsupportedSignal(sig_num) => {
+                write!(f, "Got unsupported signal: {:?}", sig_num)
+            }
+            SignalError::UnsupportedSignalName(name) => {
+                write!(f, "Got unsupported signal: {:?}", name)
+            }
+            SignalError::HandlerInstallFailed { signal, errno } => write!(
+                f,
+                "Couldn't install signal handler for {}: error {}",
+                signal, errno
+            ),
+            SignalError::ChannelClosed => write!(f, "Signal channel closed"),
+            SignalError::Uncatchable(sig) => write!(f, "Signal can't be caught: {}", sig),
+            SignalError::InvalidBufferCapacity(capacity) => {
+                write!(f, "Invalid buffer capacity: {}", capacity)
+            }
+            SignalError::HandlerAlreadySet => write!(f, "Ctrl-C handler already set"),
+            SignalError::AltStackFailed { errno } => {
+                write!(f, "Couldn't set up alternate signal stack: error {}", errno)
+            }
+            SignalError::NotifierFailed { errno } => {
+                write!(f, "Couldn't create notification pipe: error {}", errno)
+            }
+        }
+    }
+}
+
+impl error::Error for SignalError {}
+
+impl From<SignalError> for String {
+    fn from(err: SignalError) -> String {
+        err.to_string()
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{all, from_posix_signum, platform, Receiver, Signal, SignalError};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Installs signal handlers that forward to an `mpsc::Sender`.
+///
+/// Both `Sender<i32>` (signal numbers) and `Sender<Signal>` work; the typed
+/// `Signal` channel is preferred, since numbers have to be converted back
+/// with `from_posix_signum` on the other end.
+pub trait SignalSender {
+    fn prepare_signals(&self) {
+        self.prepare_signals_for(&all())
+    }
+
+    fn prepare_signals_for(&self, signals: &[Signal]);
+}
+
+impl SignalSender for mpsc::Sender<i32> {
+    fn prepare_signals_for(&self, signals: &[Signal]) {
+        let s = self.clone();
+        let numbers: Vec<i32> = signals.iter().map(Signal::to_i32).collect();
+        // The legacy API has no owner to tear it down, so the handler stays
+        // installed for the rest of the process. It has no way to report
+        // errors either; a signal that can't be handled is just never sent.
+        let _ = platform::set_handler(&numbers, move |signals| {
+            for delivery in signals {
+                // A dropped receiver just means nobody is listening anymore;
+                // that's no reason to take down the handler thread.
+                let _ = s.send(delivery.signal);
+            }
+        });
+    }
+}
+
+impl SignalSender for mpsc::Sender<Signal> {
+    fn prepare_signals_for(&self, signals: &[Signal]) {
+        let s = self.clone();
+        let numbers: Vec<i32> = signals.iter().map(Signal::to_i32).collect();
+        let _ = platform::set_handler(&numbers, move |signals| {
+            for sig in signals
+                .iter()
+                .filter_map(|d| from_posix_signum(d.signal).ok())
+            {
+                let _ = s.send(sig);
+            }
+        });
+    }
+}
+
+pub trait SignalReceiver {
+    fn listen(&self) -> Result<Signal, SignalError>;
+    fn try_listen(&self) -> Result<Option<Signal>, SignalError>;
+    fn listen_timeout(&self, dur: Duration) -> Result<Option<Signal>, SignalError>;
+}
+
+impl SignalReceiver for mpsc::Receiver<i32> {
+    fn listen(&self) -> Result<Signal, SignalError> {
+        let sig_num = self.recv().map_err(|_| SignalError::ChannelClosed)?;
+        from_posix_signum(sig_num)
+    }
+
+    fn try_listen(&self) -> Result<Option<Signal>, SignalError> {
+        match self.try_recv() {
+            Ok(sig_num) => from_posix_signum(sig_num).map(Some),
+            Err(mpsc::TryRecvError::Empty) => Ok(None),
+            Err(mpsc::TryRecvError::Disconnected) => Err(SignalError::ChannelClosed),
+        }
+    }
+
+    fn listen_timeout(&self, dur: Duration) -> Result<Option<Signal>, SignalError> {
+        match self.recv_timeout(dur) {
+            Ok(sig_num) => from_posix_signum(sig_num).map(Some),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(SignalError::ChannelClosed),
+        }
+    }
+}
+
+impl SignalReceiver for mpsc::Receiver<Signal> {
+    fn listen(&self) -> Result<Signal, SignalError> {
+        self.recv().map_err(|_| SignalError::ChannelClosed)
+    }
+
+    fn try_listen(&self) -> Result<Option<Signal>, SignalError> {
+        match self.try_recv() {
+            Ok(sig) => Ok(Some(sig)),
+            Err(mpsc::TryRecvError::Empty) => Ok(None),
+            Err(mpsc::TryRecvError::Disconnected) => Err(SignalError::ChannelClosed),
+        }
+    }
+
+    fn listen_timeout(&self, dur: Duration) -> Result<Option<Signal>, SignalError> {
+        match self.recv_timeout(dur) {
+            Ok(sig) => Ok(Some(sig)),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(SignalError::ChannelClosed),
+        }
+    }
+}
+
+impl SignalReceiver for Receiver<Signal> {
+    fn listen(&self) -> Result<Signal, SignalError> {
+        self.recv()
+    }
+
+    fn try_listen(&self) -> Result<Option<Signal>, SignalError> {
+        self.try_recv()
+    }
+
+    fn listen_timeout(&self, dur: Duration) -> Result<Option<Signal>, SignalError> {
+        self.recv_timeout(dur)
+    }
+}
+
+pub fn new() -> (mpsc::Sender<i32>, mpsc::Receiver<i32>) {
+    mpsc::channel()
+}
+
+/// Shorthand for `new()` followed by `prepare_signals()` on the sender, for
+/// the common case of a single consumer.
+pub fn handle() -> mpsc::Receiver<i32> {
+    let (signal_sender, signal_receiver) = new();
+    signal_sender.prepare_signals();
+    signal_receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listen_on_a_closed_channel_fails() {
+        let (sender, receiver) = new();
+        drop(sender);
+        assert_eq!(receiver.listen(), Err(SignalError::ChannelClosed));
+        let (sender, receiver) = mpsc::channel::<Signal>();
+        drop(sender);
+        assert_eq!(receiver.listen(), Err(SignalError::ChannelClosed));
+    }
+
+    #[test]
+    fn try_listen_reports_empty_ready_and_closed() {
+        let (sender, receiver) = new();
+        assert_eq!(receiver.try_listen(), Ok(None));
+        sender.send(Signal::Int.to_i32()).unwrap();
+        assert_eq!(receiver.try_listen(), Ok(Some(Signal::Int)));
+        drop(sender);
+        assert_eq!(receiver.try_listen(), Err(SignalError::ChannelClosed));
+
+        let (sender, receiver) = mpsc::channel();
+        assert_eq!(receiver.try_listen(), Ok(None));
+        sender.send(Signal::Term).unwrap();
+        assert_eq!(receiver.try_listen(), Ok(Some(Signal::Term)));
+        drop(sender);
+        assert_eq!(receiver.try_listen(), Err(SignalError::ChannelClosed));
+    }
+
+    #[test]
+    fn listen_timeout_tells_a_timeout_from_a_closed_channel() {
+        let short = Duration::from_millis(10);
+        let (sender, receiver) = new();
+        assert_eq!(receiver.listen_timeout(short), Ok(None));
+        sender.send(Signal::Hup.to_i32()).unwrap();
+        assert_eq!(receiver.listen_timeout(short), Ok(Some(Signal::Hup)));
+        drop(sender);
+        assert_eq!(
+            receiver.listen_timeout(short),
+            Err(SignalError::ChannelClosed)
+        );
+
+        let (sender, receiver) = mpsc::channel();
+        assert_eq!(receiver.listen_timeout(short), Ok(None));
+        sender.send(Signal::Hup).unwrap();
+        assert_eq!(receiver.listen_timeout(short), Ok(Some(Signal::Hup)));
+        drop(sender);
+        assert_eq!(
+            receiver.listen_timeout(short),
+            Err(SignalError::ChannelClosed)
+        );
+    }
+}
@@ -1,74 +1,39 @@
-use simple_signal::{self};
-use std::sync::mpsc;
-
-// Copied from https://github.com/swizard0/rust-simple-signal/blob/master/src/lib.rs
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum Signal {
-    Hup,
-    Int,
-    Quit,
-    Ill,
-    Abrt,
-    Fpe,
-    Kill,
-    Segv,
-    Pipe,
-    Alrm,
-    Term,
-}
-
-pub fn all() -> Vec<simple_signal::Signal> {
-    vec![
-        simple_signal::Signal::Hup,  // 0
-        simple_signal::Signal::Int,  // 1
-        simple_signal::Signal::Ill,  // 3
-        simple_signal::Signal::Abrt, // 4
-        simple_signal::Signal::Fpe,  // 5
-        simple_signal::Signal::Pipe, // 8
-        simple_signal::Signal::Alrm, // 9
-        simple_signal::Signal::Term, // 10
-    ]
-}
-
-pub fn from_i32(sig_num: i32) -> Result<Signal, String> {
-    match sig_num {
-        0 => Ok(Signal::Hup),
-        1 => Ok(Signal::Int),
-        3 => Ok(Signal::Ill),
-        4 => Ok(Signal::Abrt),
-        5 => Ok(Signal::Fpe),
-        8 => Ok(Signal::Pipe),
-        9 => Ok(Signal::Alrm),
-        10 => Ok(Signal::Term),
-        _ => Err(format!("Got unsupported signal: {:?}", sig_num)),
-    }
-}
-
-pub trait SignalSender {
-    fn prepare_signals(&self);
-}
-
-impl SignalSender for mpsc::Sender<i32> {
-    fn prepare_signals(&self) {
-        let s = self.clone();
-        simple_signal::set_handler(&all(), move |signals| {
-            for sig in signals {
-                s.send(*sig as i32).unwrap();
-            }
-        });
-    }
-}
-
-pub trait SignalReceiver {
-    fn listen(&self) -> Result<Signal, String>;
-}
-
-impl SignalReceiver for mpsc::Receiver<i32> {
-    fn listen(&self) -> Result<Signal, String> {
-        from_i32(self.recv().unwrap())
-    }
-}
-
-pub fn new() -> (mpsc::Sender<i32>, mpsc::Receiver<i32>) {
-    mpsc::channel()
-}
+// The crate is split in two. The `Signal` type, its conversions and its
+// classification (`signal`, `error` and `numbers`) only need `core` and
+// `alloc`, so they're always there, even without the `std` feature, for
+// code that just needs to name and classify signals. Everything that
+// actually handles signals (the platform layer, the channels, `Signals` and
+// the legacy API) needs threads and locks, and so needs `std`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod error;
+mod numbers;
+mod signal;
+
+#[cfg(feature = "std")]
+mod channel;
+#[cfg(feature = "std")]
+mod legacy;
+#[cfg(feature = "std")]
+mod platform;
+#[cfg(feature = "std")]
+mod signals;
+
+pub use error::SignalError;
+#[allow(deprecated)]
+pub use signal::from_i32;
+pub use signal::{all, from_posix_signum, is_supported, realtime, DefaultAction, Signal};
+
+#[cfg(feature = "std")]
+pub use channel::{CancelHandle, OverflowPolicy, Receiver};
+#[cfg(feature = "std")]
+pub use legacy::{handle, new, SignalReceiver, SignalSender};
+#[cfg(all(unix, feature = "std"))]
+pub use signals::MaskGuard;
+#[cfg(feature = "std")]
+pub use signals::{
+    reset_all, set_ctrl_c_handler, wait_for_shutdown, ClassifiedSignal, RawSignal, ShutdownGuard,
+    ShutdownStage, SignalInfo, Signals, SignalsBuilder,
+};
@@ -0,0 +1,49 @@
+// Signal numbers, and the range of real-time ones, for the current
+// platform. Unlike the handler machinery in `platform`, this needs nothing
+// from std, so it's available without the `std` feature.
+#[cfg(unix)]
+pub use libc::{
+    SIGABRT, SIGALRM, SIGCHLD, SIGCONT, SIGFPE, SIGHUP, SIGILL, SIGINT, SIGKILL, SIGPIPE, SIGQUIT,
+    SIGSEGV, SIGSTOP, SIGTERM, SIGTSTP, SIGUSR1, SIGUSR2, SIGWINCH,
+};
+
+// Windows' C runtime only defines a few of these, and targets without an OS
+// (embedded, wasm32-unknown-unknown) have none at all; they get the
+// conventional POSIX numbers so every variant still has one. The exception
+// is SIGABRT on Windows, whose C runtime does define it, as 22.
+#[cfg(not(unix))]
+mod fallback {
+    pub const SIGHUP: i32 = 1;
+    pub const SIGINT: i32 = 2;
+    pub const SIGQUIT: i32 = 3;
+    pub const SIGILL: i32 = 4;
+    pub const SIGFPE: i32 = 8;
+    pub const SIGKILL: i32 = 9;
+    pub const SIGUSR1: i32 = 10;
+    pub const SIGSEGV: i32 = 11;
+    pub const SIGUSR2: i32 = 12;
+    pub const SIGPIPE: i32 = 13;
+    pub const SIGALRM: i32 = 14;
+    pub const SIGTERM: i32 = 15;
+    pub const SIGCHLD: i32 = 17;
+    pub const SIGCONT: i32 = 18;
+    pub const SIGSTOP: i32 = 19;
+    pub const SIGTSTP: i32 = 20;
+    #[cfg(windows)]
+    pub const SIGABRT: i32 = 22;
+    #[cfg(not(windows))]
+    pub const SIGABRT: i32 = 6;
+    pub const SIGWINCH: i32 = 28;
+}
+#[cfg(not(unix))]
+pub use self::fallback::*;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn realtime_range() -> Option<(i32, i32)> {
+    Some((libc::SIGRTMIN(), libc::SIGRTMAX()))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn realtime_range() -> Option<(i32, i32)> {
+    None
+}
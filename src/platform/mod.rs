@@ -0,0 +1,228 @@
+// simple_signal only knows about a fixed set of signals (no SIGUSR1/SIGUSR2),
+// so the OS handler lives here instead.
+//
+// The work is split in two halves. The OS-specific half (`imp`) catches
+// signals and queues them along with who sent them. On UNIX that's one of
+// two backends: normally (`pipe`) it happens inside a signal handler of
+// ours, so it does nothing but write(2) a small record to a self-pipe,
+// which is async-signal-safe (unlike locking, allocating or sending on a
+// channel). With the `signalfd` feature on Linux (`signalfd`), there's no
+// handler at all: the signals are blocked and read from a signalfd. The
+// other half is a single worker thread which blocks until `imp` has
+// something, then hands each registered callback the signals it asked for,
+// so independent handlers can't steal each other's signals. All the real
+// work happens on that thread, never in signal context.
+//
+// Everything here works on raw signal numbers rather than `Signal`, so that
+// signals the enum doesn't model can be handled too.
+use crate::SignalError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread;
+
+#[cfg(all(unix, not(all(target_os = "linux", feature = "signalfd"))))]
+mod pipe;
+#[cfg(all(unix, not(all(target_os = "linux", feature = "signalfd"))))]
+use self::pipe as imp;
+#[cfg(all(target_os = "linux", feature = "signalfd"))]
+mod signalfd;
+#[cfg(unix)]
+pub use self::imp::{alt_stack, chain};
+#[cfg(all(target_os = "linux", feature = "signalfd"))]
+use self::signalfd as imp;
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub use self::unix::{
+    block, blocked, clear, notifier, notify, pending, raise, send_to_child, unblock, Mask,
+};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::unix::set_nice;
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+use self::windows as imp;
+#[cfg(windows)]
+pub use self::windows::{chain, raise};
+
+// Signal numbers go up to 64 on Linux (SIGRTMAX).
+pub const MAX_SIGNAL: i32 = 128;
+static START: Once = Once::new();
+static HANDLERS: Mutex<Vec<Handler>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+// How many registered handlers want each signal; the OS handler is installed
+// when a signal gains its first user and the original disposition comes back
+// when it loses its last one.
+static USERS: Mutex<Vec<(i32, usize)>> = Mutex::new(Vec::new());
+
+type Callback = Box<dyn Fn(&[Delivery]) + Send>;
+
+// A signal as the OS handler recorded it: its number and, when the OS said
+// and it makes sense for the signal, the pid and uid of the sender.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Delivery {
+    pub signal: i32,
+    pub sender: Option<(i32, u32)>,
+}
+
+struct Handler {
+    id: HandlerId,
+    signals: Vec<i32>,
+    callback: Callback,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HandlerId(usize);
+
+pub fn is_valid(sig: i32) -> bool {
+    (1..MAX_SIGNAL).contains(&sig)
+}
+
+// Fails with `NotifierFailed` if the queue signals go through can't be set
+// up, or with `HandlerInstallFailed` for a signal that couldn't be handled,
+// having undone whatever was done up to that point.
+pub fn set_handler<F>(signals: &[i32], user_handler: F) -> Result<HandlerId, SignalError>
+where
+    F: Fn(&[Delivery]) + Send + 'static,
+{
+    let id = HandlerId(NEXT_ID.fetch_add(1, Ordering::SeqCst));
+    // The queue must exist before any OS handler that writes to it does.
+    imp::init().map_err(|errno| SignalError::NotifierFailed { errno })?;
+    let mut handlers = HANDLERS.lock().unwrap();
+    acquire(&mut USERS.lock().unwrap(), signals)
+        .map_err(|(signal, errno)| SignalError::HandlerInstallFailed { signal, errno })?;
+    handlers.push(Handler {
+        id,
+        signals: signals.to_vec(),
+        callback: Box::new(user_handler),
+    });
+    START.call_once(|| {
+        thread::Builder::new()
+            .name(String::from("signal-msg"))
+            .spawn(dispatch)
+            .expect("failed to spawn thread");
+    });
+    Ok(id)
+}
+
+pub fn remove_handler(id: HandlerId) {
+    let removed = {
+        let mut handlers = HANDLERS.lock().unwrap();
+        match handlers.iter().position(|h| h.id == id) {
+            Some(pos) => handlers.remove(pos),
+            None => return,
+        }
+    };
+    release(&mut USERS.lock().unwrap(), &removed.signals);
+}
+
+// Changes the signals the handler `id` gets to `signals`, installing OS
+// handlers for the new ones and restoring the ones nobody uses anymore.
+// Fails like `set_handler`, leaving everything as it was.
+pub fn set_signals(id: HandlerId, signals: &[i32]) -> Result<(), (i32, i32)> {
+    let mut handlers = HANDLERS.lock().unwrap();
+    let handler = match handlers.iter_mut().find(|h| h.id == id) {
+        Some(handler) => handler,
+        None => return Ok(()),
+    };
+    let added: Vec<i32> = signals
+        .iter()
+        .filter(|sig| !handler.signals.contains(sig))
+        .copied()
+        .collect();
+    let removed: Vec<i32> = handler
+        .signals
+        .iter()
+        .filter(|sig| !signals.contains(sig))
+        .copied()
+        .collect();
+    let mut users = USERS.lock().unwrap();
+    acquire(&mut users, &added)?;
+    release(&mut users, &removed);
+    handler.signals = signals.to_vec();
+    Ok(())
+}
+
+// Takes `sig` away from the handler `id`, restoring its disposition if
+// nobody else uses it.
+pub fn release_signal(id: HandlerId, sig: i32) {
+    let mut handlers = HANDLERS.lock().unwrap();
+    if let Some(h) = handlers.iter_mut().find(|h| h.id == id) {
+        if let Some(pos) = h.signals.iter().position(|s| *s == sig) {
+            h.signals.remove(pos);
+            release(&mut USERS.lock().unwrap(), &[sig]);
+        }
+    }
+}
+
+// Takes one more use of each of `signals`, installing the OS handler for the
+// ones nobody used yet. If that fails for one of them, the uses taken so far
+// are given up again.
+fn acquire(users: &mut Vec<(i32, usize)>, signals: &[i32]) -> Result<(), (i32, i32)> {
+    for (i, sig) in signals.iter().enumerate() {
+        match users.iter_mut().find(|(s, _)| s == sig) {
+            Some((_, count)) => *count += 1,
+            None => {
+                if let Err(errno) = imp::install(*sig) {
+                    release(users, &signals[..i]);
+                    return Err((*sig, errno));
+                }
+                users.push((*sig, 1));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Gives up one use of each of `signals`, restoring the ones nobody uses
+// anymore.
+fn release(users: &mut Vec<(i32, usize)>, signals: &[i32]) {
+    for sig in signals {
+        if let Some(pos) = users.iter().position(|(s, _)| s == sig) {
+            users[pos].1 -= 1;
+            if users[pos].1 == 0 {
+                imp::restore(*sig);
+                users.remove(pos);
+            }
+        }
+    }
+}
+
+// Puts every signal that was ever handled back to its default disposition.
+// Registered handlers stay registered but lose their signals, so they hear
+// nothing more and can still be removed as usual.
+pub fn reset_all() {
+    let mut handlers = HANDLERS.lock().unwrap();
+    let mut users = USERS.lock().unwrap();
+    imp::reset_all();
+    users.clear();
+    for h in handlers.iter_mut() {
+        h.signals.clear();
+    }
+}
+
+// Sets up a thread the crate starts for itself, before it does anything
+// else. With the signalfd backend that blocks every signal in it, so that
+// none is ever delivered to it instead of the signalfd.
+pub fn own_thread() {
+    imp::own_thread();
+}
+
+fn dispatch() {
+    own_thread();
+    let mut signals = Vec::new();
+    let mut wanted = Vec::new();
+    loop {
+        signals.clear();
+        imp::wait(&mut signals);
+        for h in HANDLERS.lock().unwrap().iter() {
+            wanted.clear();
+            wanted.extend(signals.iter().filter(|d| h.signals.contains(&d.signal)));
+            if !wanted.is_empty() {
+                (h.callback)(&wanted);
+            }
+        }
+    }
+}
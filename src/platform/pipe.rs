@@ -0,0 +1,275 @@
+// The backend used unless the `signalfd` feature is on: our own handler is
+// installed with sigaction(2) and passes signals on through a self-pipe.
+use super::unix::{self, errno_location, last_errno};
+use super::{is_valid, Delivery, MAX_SIGNAL};
+use libc::{c_int, c_void, sighandler_t, siginfo_t};
+use std::convert::TryFrom;
+use std::io;
+use std::mem;
+use std::os::unix::io::IntoRawFd;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// The dispositions in place before our handler took over, so they can be put
+// back once nobody needs the signal anymore.
+static PREVIOUS: Mutex<Vec<(c_int, libc::sigaction)>> = Mutex::new(Vec::new());
+// The same handlers again, for calling from the signal handler when
+// chaining, which can't take a lock: the previous handler's address and
+// flags, and how many instances asked for that signal to be chained.
+static PREVIOUS_HANDLER: [AtomicUsize; MAX_SIGNAL as usize] =
+    [const { AtomicUsize::new(0) }; MAX_SIGNAL as usize];
+static PREVIOUS_FLAGS: [AtomicUsize; MAX_SIGNAL as usize] =
+    [const { AtomicUsize::new(0) }; MAX_SIGNAL as usize];
+static CHAINERS: [AtomicUsize; MAX_SIGNAL as usize] =
+    [const { AtomicUsize::new(0) }; MAX_SIGNAL as usize];
+// Set once an alternate signal stack has been asked for, from then on our
+// handler is installed with SA_ONSTACK.
+static ON_STACK: AtomicBool = AtomicBool::new(false);
+// Every signal a handler was ever installed for, for `reset_all`.
+static TOUCHED: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+
+// The self-pipe: the handler writes one record per signal to `WRITE_FD` and
+// the worker thread reads them from `READ_FD`. A record is four native
+// endian i32s: the signal number, whether the sender is known, its pid and
+// its uid. Writes this small are atomic, so records never interleave, and
+// as the reads below are a whole number of records long, they never
+// return a partial one either.
+const RECORD: usize = 16;
+// Set once the pipe has been created.
+static PIPE: Mutex<bool> = Mutex::new(false);
+static READ_FD: AtomicI32 = AtomicI32::new(-1);
+static WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+// Only async-signal-safe calls are allowed in here: atomic loads, reading
+// `info` and write(2). The pipe's write end is non-blocking, so if it's ever
+// full the signal is dropped rather than deadlocking the interrupted thread.
+// errno is put back afterwards so the interrupted code doesn't see it
+// change.
+extern "C" fn handler(sig: c_int, info: *mut siginfo_t, context: *mut c_void) {
+    unsafe {
+        let errno = *errno_location();
+        let record = record(sig, info);
+        libc::write(
+            WRITE_FD.load(Ordering::Relaxed),
+            record.as_ptr() as *const c_void,
+            RECORD,
+        );
+        *errno_location() = errno;
+        call_previous(sig, info, context);
+    }
+}
+
+// Passes the signal on to the handler that was installed before ours, if
+// chaining was asked for. There's nothing to call for SIG_DFL or SIG_IGN:
+// the default action is exactly what catching the signal is meant to
+// prevent, so both are skipped.
+unsafe fn call_previous(sig: c_int, info: *mut siginfo_t, context: *mut c_void) {
+    let n = sig as usize;
+    if n >= CHAINERS.len() || CHAINERS[n].load(Ordering::SeqCst) == 0 {
+        return;
+    }
+    let previous = PREVIOUS_HANDLER[n].load(Ordering::SeqCst);
+    if previous == libc::SIG_DFL || previous == libc::SIG_IGN {
+        return;
+    }
+    if PREVIOUS_FLAGS[n].load(Ordering::SeqCst) & libc::SA_SIGINFO as usize != 0 {
+        let f: extern "C" fn(c_int, *mut siginfo_t, *mut c_void) = mem::transmute(previous);
+        f(sig, info, context);
+    } else {
+        let f: extern "C" fn(c_int) = mem::transmute(previous);
+        f(sig);
+    }
+}
+
+// Counts one more (or one fewer) user who wants each of `signals` chained.
+pub fn chain(signals: &[i32], on: bool) {
+    for sig in signals.iter().filter(|sig| is_valid(**sig)) {
+        let chainers = &CHAINERS[*sig as usize];
+        if on {
+            chainers.fetch_add(1, Ordering::SeqCst);
+        } else {
+            chainers.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+// The sender is only meaningful for signals sent with kill(2) and friends
+// (a non-positive si_code) and for SIGCHLD, where it's the child; for
+// signals like SIGSEGV the kernel raised itself those fields are garbage.
+unsafe fn record(sig: c_int, info: *const siginfo_t) -> [u8; RECORD] {
+    let mut words = [sig, 0, 0, 0];
+    if !info.is_null() && ((*info).si_code <= 0 || sig == libc::SIGCHLD) {
+        words[1] = 1;
+        words[2] = (*info).si_pid();
+        words[3] = (*info).si_uid() as i32;
+    }
+    let mut bytes = [0u8; RECORD];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(&words) {
+        chunk.copy_from_slice(&word.to_ne_bytes());
+    }
+    bytes
+}
+
+// Creates the self-pipe, the first time it's called. Fails with the errno
+// that pipe(2) or fcntl(2) set, e.g. EMFILE if the process is out of file
+// descriptors, leaving nothing behind, so a later call can try again.
+pub fn init() -> Result<(), i32> {
+    let mut ready = PIPE.lock().unwrap();
+    if *ready {
+        return Ok(());
+    }
+    let (read, write) = unix::pipe(false).map_err(|err| err.raw_os_error().unwrap_or(0))?;
+    READ_FD.store(read.into_raw_fd(), Ordering::SeqCst);
+    WRITE_FD.store(write.into_raw_fd(), Ordering::SeqCst);
+    *ready = true;
+    Ok(())
+}
+
+// Blocks until at least one signal has arrived and appends everything that's
+// queued to `signals`. This is what listeners are ultimately waiting on, and
+// it runs in a process that's getting signals, so being interrupted by one
+// (EINTR) is routine and never ends the wait; only a real signal does. Any
+// other failure means the pipe is gone, which nothing can recover from, so
+// it panics rather than spinning on an error that will never go away.
+pub fn wait(signals: &mut Vec<Delivery>) {
+    if let Err(err) = read_pipe(signals) {
+        panic!("couldn't read signal pipe: {}", err);
+    }
+}
+
+// Reads what the self-pipe has, blocking until it has something. A read
+// interrupted by a signal is retried; the write end is never closed, so
+// reaching the end of the pipe is an error too.
+fn read_pipe(signals: &mut Vec<Delivery>) -> io::Result<()> {
+    let mut buf = [0u8; RECORD * 64];
+    let n = loop {
+        let n = unsafe {
+            libc::read(
+                READ_FD.load(Ordering::SeqCst),
+                buf.as_mut_ptr() as *mut c_void,
+                buf.len(),
+            )
+        };
+        if n > 0 {
+            break n;
+        }
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if last_errno() != libc::EINTR {
+            return Err(io::Error::last_os_error());
+        }
+    };
+    signals.extend(
+        buf[..n as usize]
+            .chunks_exact(RECORD)
+            .map(parse)
+            .filter(|d| is_valid(d.signal)),
+    );
+    Ok(())
+}
+
+fn parse(record: &[u8]) -> Delivery {
+    let word = |i: usize| {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&record[i * 4..i * 4 + 4]);
+        i32::from_ne_bytes(bytes)
+    };
+    Delivery {
+        signal: word(0),
+        sender: if word(1) != 0 {
+            Some((word(2), word(3) as u32))
+        } else {
+            None
+        },
+    }
+}
+
+// Our handler, as handed to sigaction(2). It's always installed with
+// sigaction rather than signal(2), whose semantics vary: some systems reset
+// the disposition to SIG_DFL before running the handler (as SysV did), so
+// the next signal would get the default action. The flags are:
+//
+// * SA_SIGINFO, to be told who sent the signal;
+// * SA_RESTART, so system calls the signal interrupts are restarted rather
+//   than failing with EINTR in code that never asked for signals;
+// * SA_ONSTACK, once an alternate stack was asked for (see `alt_stack`).
+//
+// SA_RESETHAND is deliberately not among them, so the handler stays
+// installed for every delivery, and SA_NODEFER neither, so the handler is
+// never reentered for the signal it's handling.
+unsafe fn action() -> libc::sigaction {
+    let mut action: libc::sigaction = mem::zeroed();
+    action.sa_sigaction =
+        handler as extern "C" fn(c_int, *mut siginfo_t, *mut c_void) as sighandler_t;
+    action.sa_flags = libc::SA_RESTART | libc::SA_SIGINFO;
+    if ON_STACK.load(Ordering::SeqCst) {
+        action.sa_flags |= libc::SA_ONSTACK;
+    }
+    libc::sigemptyset(&mut action.sa_mask);
+    action
+}
+
+// Fails with the errno sigaction(2) set, e.g. EINVAL for a signal that
+// can't be caught.
+pub fn install(signum: i32) -> Result<(), i32> {
+    unsafe {
+        let action = action();
+        let mut old: libc::sigaction = mem::zeroed();
+        if libc::sigaction(signum, &action, &mut old) != 0 {
+            return Err(last_errno());
+        }
+        PREVIOUS.lock().unwrap().push((signum, old));
+        if let Some(n) = usize::try_from(signum)
+            .ok()
+            .filter(|n| *n < MAX_SIGNAL as usize)
+        {
+            PREVIOUS_HANDLER[n].store(old.sa_sigaction, Ordering::SeqCst);
+            PREVIOUS_FLAGS[n].store(old.sa_flags as usize, Ordering::SeqCst);
+        }
+        let mut touched = TOUCHED.lock().unwrap();
+        if !touched.contains(&signum) {
+            touched.push(signum);
+        }
+        Ok(())
+    }
+}
+
+// Gives the calling thread an alternate signal stack of `size` bytes (see
+// `unix::alt_stack`) and has our handler run on it from now on, reinstalling
+// it with SA_ONSTACK for the signals it already handles.
+pub fn alt_stack(size: usize) -> Result<(), i32> {
+    unix::alt_stack(size)?;
+    ON_STACK.store(true, Ordering::SeqCst);
+    unsafe {
+        let action = action();
+        for (signum, _) in PREVIOUS.lock().unwrap().iter() {
+            libc::sigaction(*signum, &action, ptr::null_mut());
+        }
+    }
+    Ok(())
+}
+
+pub fn restore(signum: i32) {
+    let mut previous = PREVIOUS.lock().unwrap();
+    if let Some(pos) = previous.iter().position(|(s, _)| *s == signum) {
+        let (_, old) = previous.remove(pos);
+        unsafe {
+            libc::sigaction(signum, &old, ptr::null_mut());
+        }
+    }
+}
+
+pub fn reset_all() {
+    let mut previous = PREVIOUS.lock().unwrap();
+    for signum in TOUCHED.lock().unwrap().iter() {
+        unsafe {
+            libc::signal(*signum, libc::SIG_DFL);
+        }
+    }
+    previous.clear();
+}
+
+// Signals can go to any thread, so there's nothing to set up in ours.
+pub fn own_thread() {}
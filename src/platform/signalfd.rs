@@ -0,0 +1,194 @@
+// The backend used with the `signalfd` feature on Linux. No handler is
+// installed and the dispositions are left alone: handled signals are
+// blocked instead, in the thread that asks for them (and so in the threads
+// it starts from then on, which inherit its mask), and the worker thread
+// reads them from a signalfd(2). A signal only reaches the signalfd while
+// every thread blocks it; one that some thread doesn't block is delivered
+// to that thread and gets its disposition as usual, normally the default
+// action. That's why the signals have to be handled before the program
+// starts any threads, and why the threads this crate starts block every
+// signal (see `own_thread`).
+use super::unix::{self, last_errno};
+use super::Delivery;
+use libc::c_int;
+use std::cell::Cell;
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+
+static FD: AtomicI32 = AtomicI32::new(-1);
+// The signals the signalfd is reading, i.e. its mask.
+static SIGNALS: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+
+thread_local! {
+    // Set in the threads this crate started, which block every signal.
+    static OWN_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+const SIZE: usize = mem::size_of::<libc::signalfd_siginfo>();
+
+fn set_of(signals: &[c_int]) -> libc::sigset_t {
+    unsafe {
+        let mut set: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut set);
+        for sig in signals {
+            libc::sigaddset(&mut set, *sig);
+        }
+        set
+    }
+}
+
+// Gives the signalfd `signals` as its mask, creating it the first time.
+fn set_mask(signals: &[c_int]) -> Result<(), i32> {
+    let set = set_of(signals);
+    let fd = unsafe {
+        libc::signalfd(
+            FD.load(Ordering::SeqCst),
+            &set,
+            libc::SFD_NONBLOCK | libc::SFD_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return Err(last_errno());
+    }
+    FD.store(fd, Ordering::SeqCst);
+    Ok(())
+}
+
+fn set_blocked(signals: &[c_int], how: c_int) {
+    let set = set_of(signals);
+    unsafe {
+        libc::pthread_sigmask(how, &set, ptr::null_mut());
+    }
+}
+
+// Creates the signalfd, the first time it's called, before the worker thread
+// first waits, so the descriptor it polls exists from the start. Fails with
+// the errno signalfd(2) set, leaving nothing behind.
+pub fn init() -> Result<(), i32> {
+    let _signals = SIGNALS.lock().unwrap();
+    if FD.load(Ordering::SeqCst) >= 0 {
+        return Ok(());
+    }
+    set_mask(&[])
+}
+
+// Blocks all signals, for good, in a thread this crate starts: it never
+// needs any, and a signal it didn't block could be delivered to it rather
+// than read from the signalfd. That includes signals only handled later on,
+// which is why it's every signal and not just the ones handled so far.
+pub fn own_thread() {
+    OWN_THREAD.with(|own| own.set(true));
+    unsafe {
+        let mut set: libc::sigset_t = mem::zeroed();
+        libc::sigfillset(&mut set);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, ptr::null_mut());
+    }
+}
+
+// Fails with the errno signalfd(2) set.
+pub fn install(signum: c_int) -> Result<(), i32> {
+    let mut signals = SIGNALS.lock().unwrap();
+    signals.push(signum);
+    if let Err(errno) = set_mask(&signals) {
+        signals.pop();
+        return Err(errno);
+    }
+    set_blocked(&[signum], libc::SIG_BLOCK);
+    Ok(())
+}
+
+// Only the calling thread gets to unblock the signal again; other threads
+// that inherited the mask keep it blocked, and a signal no thread accepts
+// stays pending rather than getting its disposition. There's no way to
+// change another thread's mask, so this is documented instead. The
+// crate's own threads keep blocking it, so that they still never take a
+// signal if it's handled again later.
+pub fn restore(signum: c_int) {
+    let mut signals = SIGNALS.lock().unwrap();
+    signals.retain(|s| *s != signum);
+    let _ = set_mask(&signals);
+    if !OWN_THREAD.with(Cell::get) {
+        set_blocked(&[signum], libc::SIG_UNBLOCK);
+    }
+}
+
+pub fn reset_all() {
+    let mut signals = SIGNALS.lock().unwrap();
+    if !OWN_THREAD.with(Cell::get) {
+        set_blocked(&signals, libc::SIG_UNBLOCK);
+    }
+    signals.clear();
+    let _ = set_mask(&signals);
+}
+
+// There's no handler of ours to run on the stack, but the thread gets it all
+// the same, so that asking for one works, and fails, as it does otherwise.
+pub fn alt_stack(size: usize) -> Result<(), i32> {
+    unix::alt_stack(size)
+}
+
+// There's no handler of ours to chain from: a signal that reaches the
+// signalfd never runs any handler at all.
+pub fn chain(_signals: &[i32], _on: bool) {}
+
+// Blocks until the signalfd has something and appends all of it to
+// `signals`. The worker thread blocks every signal, so nothing can interrupt
+// it, but an EINTR would just go round again. Any other failure means the
+// signalfd is gone, which nothing can recover from, so it panics rather than
+// spinning on an error that will never go away.
+pub fn wait(signals: &mut Vec<Delivery>) {
+    while signals.is_empty() {
+        let mut fds = [libc::pollfd {
+            fd: FD.load(Ordering::SeqCst),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        if unsafe { libc::poll(fds.as_mut_ptr(), 1, -1) } < 0 {
+            if last_errno() == libc::EINTR {
+                continue;
+            }
+            panic!("couldn't poll signalfd: {}", io::Error::last_os_error());
+        }
+        read(signals);
+    }
+}
+
+// Appends whatever the signalfd has to `signals`, without blocking, going on
+// if a read is interrupted by a signal. The sender is only filled in for
+// signals sent with kill(2) and friends (a non-positive code) and for
+// SIGCHLD, where it's the child, like the kernel does for siginfo_t.
+fn read(signals: &mut Vec<Delivery>) {
+    let mut buf = [0u8; SIZE * 16];
+    loop {
+        let n = unsafe {
+            libc::read(
+                FD.load(Ordering::SeqCst),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        };
+        if n < 0 && last_errno() == libc::EINTR {
+            continue;
+        }
+        if n <= 0 {
+            return;
+        }
+        for chunk in buf[..n as usize].chunks_exact(SIZE) {
+            let info: libc::signalfd_siginfo =
+                unsafe { ptr::read_unaligned(chunk.as_ptr() as *const libc::signalfd_siginfo) };
+            let signal = info.ssi_signo as c_int;
+            let known = info.ssi_code <= 0 || signal == libc::SIGCHLD;
+            signals.push(Delivery {
+                signal,
+                sender: if known {
+                    Some((info.ssi_pid as i32, info.ssi_uid))
+                } else {
+                    None
+                },
+            });
+        }
+    }
+}
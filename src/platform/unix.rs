@@ -0,0 +1,195 @@
+// What both UNIX backends (`pipe` and `signalfd`) share.
+use super::MAX_SIGNAL;
+use libc::{c_int, c_void, siginfo_t};
+use std::io;
+use std::mem;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::ptr;
+
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+pub use libc::___errno as errno_location;
+#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+pub use libc::__errno as errno_location;
+#[cfg(any(target_os = "linux", target_os = "emscripten", target_os = "redox"))]
+pub use libc::__errno_location as errno_location;
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly"
+))]
+pub use libc::__error as errno_location;
+
+// A new pipe with both ends close-on-exec. The write end is non-blocking,
+// and so is the read end if `nonblocking_read` is set.
+pub fn pipe(nonblocking_read: bool) -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0 as c_int; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let (read, write) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+    configure(&read, nonblocking_read)?;
+    configure(&write, true)?;
+    Ok((read, write))
+}
+
+fn configure(fd: &OwnedFd, nonblocking: bool) -> io::Result<()> {
+    let fd = fd.as_raw_fd();
+    unsafe {
+        if libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        if nonblocking {
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            if flags == -1 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+    }
+    Ok(())
+}
+
+// A pipe for telling someone outside the crate that something is ready:
+// `notify` makes the read end readable and `clear` empties it again. Both
+// ends are non-blocking, so neither ever waits: once the pipe is full,
+// waking the reader up again has no further effect anyway.
+pub fn notifier() -> io::Result<(OwnedFd, OwnedFd)> {
+    pipe(true)
+}
+
+pub fn notify(write: &OwnedFd) {
+    unsafe {
+        libc::write(write.as_raw_fd(), [0u8].as_ptr() as *const c_void, 1);
+    }
+}
+
+// Being interrupted by a signal doesn't stop it early, or the pipe would be
+// left readable.
+pub fn clear(read: &OwnedFd) {
+    let mut buf = [0u8; 64];
+    loop {
+        let n = unsafe { libc::read(read.as_raw_fd(), buf.as_mut_ptr() as *mut c_void, buf.len()) };
+        if n > 0 {
+            continue;
+        }
+        if n == 0 || last_errno() != libc::EINTR {
+            return;
+        }
+    }
+}
+
+pub fn last_errno() -> i32 {
+    io::Error::last_os_error().raw_os_error().unwrap_or(0)
+}
+
+// Gives the calling thread an alternate signal stack of `size` bytes. The
+// stack is never freed: the thread may still be running on it long after
+// whoever asked for it is gone. Fails with the errno sigaltstack(2) set,
+// e.g. ENOMEM if `size` is too small.
+pub fn alt_stack(size: usize) -> Result<(), i32> {
+    let memory = Box::leak(vec![0u8; size].into_boxed_slice());
+    unsafe {
+        let mut stack: libc::stack_t = mem::zeroed();
+        stack.ss_sp = memory.as_mut_ptr() as *mut c_void;
+        stack.ss_size = size;
+        if libc::sigaltstack(&stack, ptr::null_mut()) != 0 {
+            let errno = last_errno();
+            drop(Box::from_raw(memory));
+            return Err(errno);
+        }
+    }
+    Ok(())
+}
+
+// The calling thread's signal mask as it was before `block`, to be handed
+// back to `unblock`.
+pub struct Mask(libc::sigset_t);
+
+// Adds `signals` to the calling thread's signal mask.
+pub fn block(signals: &[i32]) -> Mask {
+    unsafe {
+        let mut set: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut set);
+        for sig in signals {
+            libc::sigaddset(&mut set, *sig);
+        }
+        let mut old: libc::sigset_t = mem::zeroed();
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, &mut old);
+        Mask(old)
+    }
+}
+
+// The calling thread's blocked signals, leaving its mask as it is.
+pub fn blocked() -> Vec<i32> {
+    unsafe {
+        let mut set: libc::sigset_t = mem::zeroed();
+        libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), &mut set);
+        members(&set)
+    }
+}
+
+// The signals pending for the calling thread or the process.
+pub fn pending() -> Vec<i32> {
+    unsafe {
+        let mut set: libc::sigset_t = mem::zeroed();
+        libc::sigpending(&mut set);
+        members(&set)
+    }
+}
+
+fn members(set: &libc::sigset_t) -> Vec<i32> {
+    (1..MAX_SIGNAL)
+        .filter(|sig| unsafe { libc::sigismember(set, *sig) } == 1)
+        .collect()
+}
+
+pub fn unblock(mask: &Mask) {
+    unsafe {
+        libc::pthread_sigmask(libc::SIG_SETMASK, &mask.0, ptr::null_mut());
+    }
+}
+
+// Sets the calling thread's niceness. On Linux, unlike elsewhere, each thread
+// has a priority of its own, known by its thread id.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn set_nice(level: i32) {
+    unsafe {
+        let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+        libc::setpriority(libc::PRIO_PROCESS, tid, level);
+    }
+}
+
+// Sends `signum` to the child process `pid`, unless it has exited. Returns
+// false once it has, whether or not it has been waited for yet: waitid(2)
+// with WNOWAIT only looks, leaving the child for its owner to reap, and
+// fails with ECHILD once it has been. Being past that point means its pid
+// may belong to an unrelated process by now. Other failures of kill(2)
+// (e.g. EPERM) are ignored.
+pub fn send_to_child(pid: i32, signum: i32) -> bool {
+    unsafe {
+        let mut info: siginfo_t = mem::zeroed();
+        let flags = libc::WEXITED | libc::WNOHANG | libc::WNOWAIT;
+        if libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, flags) != 0 {
+            return false;
+        }
+        // With WNOHANG, a child that's still running leaves `info` zeroed.
+        if info.si_pid() != 0 {
+            return false;
+        }
+        libc::kill(pid, signum) == 0 || *errno_location() != libc::ESRCH
+    }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "signalfd")))]
+pub fn raise(signum: i32) -> bool {
+    unsafe { libc::raise(signum) == 0 }
+}
+
+// raise(3) sends the signal to the calling thread only, which would leave it
+// pending forever if that thread blocks it for the signalfd: a signalfd only
+// sees signals sent to the process or to the thread reading it. Sending it
+// to the process gets it delivered somewhere either way.
+#[cfg(all(target_os = "linux", feature = "signalfd"))]
+pub fn raise(signum: i32) -> bool {
+    unsafe { libc::kill(libc::getpid(), signum) == 0 }
+}
@@ -0,0 +1,106 @@
+// Windows has no UNIX signals to speak of, but console programs get control
+// events for Ctrl-C and Ctrl-Break. Those are mapped to SIGINT and SIGTERM
+// respectively; every other signal is accepted but never delivered.
+use super::Delivery;
+use crate::numbers::{SIGINT, SIGTERM};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Condvar, Mutex, Once};
+use windows_sys::Win32::Foundation::{GetLastError, BOOL, FALSE, TRUE};
+use windows_sys::Win32::System::Console::{
+    GenerateConsoleCtrlEvent, SetConsoleCtrlHandler, CTRL_BREAK_EVENT, CTRL_C_EVENT,
+};
+
+static INSTALL: Once = Once::new();
+static INSTALL_ERROR: AtomicU32 = AtomicU32::new(0);
+static QUEUE: Mutex<VecDeque<i32>> = Mutex::new(VecDeque::new());
+static READY: Condvar = Condvar::new();
+static WANT_INT: AtomicBool = AtomicBool::new(false);
+static WANT_TERM: AtomicBool = AtomicBool::new(false);
+
+// Windows calls the control handler on a thread of its own rather than in an
+// interrupt-like context, so a plain queue under a mutex is all it takes.
+fn notify(sig: i32) {
+    QUEUE.lock().unwrap().push_back(sig);
+    READY.notify_one();
+}
+
+pub fn init() -> Result<(), i32> {
+    Ok(())
+}
+
+// Console events go to a thread of their own, so there's nothing to set up.
+pub fn own_thread() {}
+
+// Console events don't say who sent them.
+pub fn wait(signals: &mut Vec<Delivery>) {
+    let mut queue = QUEUE.lock().unwrap();
+    while queue.is_empty() {
+        queue = READY.wait(queue).unwrap();
+    }
+    signals.extend(queue.drain(..).map(|signal| Delivery {
+        signal,
+        sender: None,
+    }));
+}
+
+unsafe extern "system" fn ctrl_handler(ctrl_type: u32) -> BOOL {
+    match ctrl_type {
+        CTRL_C_EVENT if WANT_INT.load(Ordering::SeqCst) => {
+            notify(SIGINT);
+            TRUE
+        }
+        CTRL_BREAK_EVENT if WANT_TERM.load(Ordering::SeqCst) => {
+            notify(SIGTERM);
+            TRUE
+        }
+        _ => FALSE,
+    }
+}
+
+// Fails with the error SetConsoleCtrlHandler reported, if it did.
+pub fn install(signum: i32) -> Result<(), i32> {
+    INSTALL.call_once(|| unsafe {
+        if SetConsoleCtrlHandler(Some(ctrl_handler), TRUE) == 0 {
+            INSTALL_ERROR.store(GetLastError(), Ordering::SeqCst);
+        }
+    });
+    match INSTALL_ERROR.load(Ordering::SeqCst) {
+        0 => {}
+        err => return Err(err as i32),
+    }
+    match signum {
+        SIGINT => WANT_INT.store(true, Ordering::SeqCst),
+        SIGTERM => WANT_TERM.store(true, Ordering::SeqCst),
+        _ => {}
+    }
+    Ok(())
+}
+
+pub fn restore(signum: i32) {
+    match signum {
+        SIGINT => WANT_INT.store(false, Ordering::SeqCst),
+        SIGTERM => WANT_TERM.store(false, Ordering::SeqCst),
+        _ => {}
+    }
+}
+
+pub fn reset_all() {
+    WANT_INT.store(false, Ordering::SeqCst);
+    WANT_TERM.store(false, Ordering::SeqCst);
+}
+
+// Other console control handlers are chained by Windows itself (one that
+// returns FALSE passes the event on), so there is nothing to do here.
+pub fn chain(_signals: &[i32], _on: bool) {}
+
+// Only the two signals backed by console events can be sent; process group 0
+// is every process sharing this console, which includes this one.
+pub fn raise(signum: i32) -> bool {
+    let event = match signum {
+        SIGINT => CTRL_C_EVENT,
+        SIGTERM => CTRL_BREAK_EVENT,
+        _ => return false,
+    };
+    unsafe { GenerateConsoleCtrlEvent(event, 0) != 0 }
+}
@@ -0,0 +1,1439 @@
+use crate::numbers::{
+    self, SIGABRT, SIGALRM, SIGCHLD, SIGCONT, SIGFPE, SIGHUP, SIGILL, SIGINT, SIGKILL, SIGPIPE,
+    SIGQUIT, SIGSEGV, SIGSTOP, SIGTERM, SIGTSTP, SIGUSR1, SIGUSR2, SIGWINCH,
+};
+#[cfg(feature = "std")]
+use crate::platform;
+use crate::SignalError;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp;
+use core::convert::TryFrom;
+use core::ffi::CStr;
+use core::fmt;
+use core::str::FromStr;
+
+// Originally copied from https://github.com/swizard0/rust-simple-signal/blob/master/src/lib.rs
+//
+// Signals are ordered by their number on the current platform (see the `Ord`
+// impl below), not by the order of the variants here.
+/// A signal this crate knows by name. More may be added in later versions,
+/// so the enum is `#[non_exhaustive]`: matching on it outside this crate
+/// takes a wildcard arm. See `all_variants`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Signal {
+    Hup,
+    Int,
+    Quit,
+    Ill,
+    Abrt,
+    Fpe,
+    Kill,
+    Segv,
+    Pipe,
+    Alrm,
+    Term,
+    Usr1,
+    Usr2,
+    Chld,
+    Winch,
+    Cont,
+    Tstp,
+    Stop,
+    /// A real-time signal, as an offset from SIGRTMIN: `Realtime(0)` is
+    /// SIGRTMIN itself. Real-time signals only exist on some platforms (e.g.
+    /// Linux) and their range is only known at runtime; see `realtime()`.
+    Realtime(u8),
+}
+
+impl Signal {
+    pub fn to_i32(&self) -> i32 {
+        match self {
+            Signal::Hup => SIGHUP,
+            Signal::Int => SIGINT,
+            Signal::Quit => SIGQUIT,
+            Signal::Ill => SIGILL,
+            Signal::Abrt => SIGABRT,
+            Signal::Fpe => SIGFPE,
+            Signal::Kill => SIGKILL,
+            Signal::Segv => SIGSEGV,
+            Signal::Pipe => SIGPIPE,
+            Signal::Alrm => SIGALRM,
+            Signal::Term => SIGTERM,
+            Signal::Usr1 => SIGUSR1,
+            Signal::Usr2 => SIGUSR2,
+            Signal::Chld => SIGCHLD,
+            Signal::Winch => SIGWINCH,
+            Signal::Cont => SIGCONT,
+            Signal::Tstp => SIGTSTP,
+            Signal::Stop => SIGSTOP,
+            // There's no number to give on platforms without real-time
+            // signals; -1 is never a valid signal, so it's rejected wherever
+            // it ends up.
+            Signal::Realtime(offset) => match numbers::realtime_range() {
+                Some((min, _)) => min + i32::from(*offset),
+                None => -1,
+            },
+        }
+    }
+
+    /// The signal's number as the OS knows it; the same as `to_i32`.
+    pub fn raw(&self) -> i32 {
+        self.to_i32()
+    }
+
+    // The `Display` name, except that real-time signals are all "SIGRTMIN";
+    // `Display` adds the offset. It's `c_name` without the NUL.
+    fn name(&self) -> &'static str {
+        // Every name is ASCII, so this never fails.
+        core::str::from_utf8(self.c_name().to_bytes()).unwrap_or("")
+    }
+
+    /// The same name as a NUL-terminated C string, e.g. `"SIGINT\0"`, for
+    /// handing across an FFI boundary without allocating. Real-time signals
+    /// are all `"SIGRTMIN"`, as the offset can't be part of a static
+    /// string.
+    pub fn c_name(&self) -> &'static CStr {
+        let name: &'static [u8] = match self {
+            Signal::Hup => b"SIGHUP\0",
+            Signal::Int => b"SIGINT\0",
+            Signal::Quit => b"SIGQUIT\0",
+            Signal::Ill => b"SIGILL\0",
+            Signal::Abrt => b"SIGABRT\0",
+            Signal::Fpe => b"SIGFPE\0",
+            Signal::Kill => b"SIGKILL\0",
+            Signal::Segv => b"SIGSEGV\0",
+            Signal::Pipe => b"SIGPIPE\0",
+            Signal::Alrm => b"SIGALRM\0",
+            Signal::Term => b"SIGTERM\0",
+            Signal::Usr1 => b"SIGUSR1\0",
+            Signal::Usr2 => b"SIGUSR2\0",
+            Signal::Chld => b"SIGCHLD\0",
+            Signal::Winch => b"SIGWINCH\0",
+            Signal::Cont => b"SIGCONT\0",
+            Signal::Tstp => b"SIGTSTP\0",
+            Signal::Stop => b"SIGSTOP\0",
+            Signal::Realtime(_) => b"SIGRTMIN\0",
+        };
+        // Each name ends in its only NUL, so this never fails either.
+        CStr::from_bytes_with_nul(name).unwrap_or_default()
+    }
+
+    /// The name without the "SIG" prefix, e.g. `"HUP"`, as used by `trap`
+    /// and some job control protocols; `FromStr` accepts it too. Real-time
+    /// signals are all `"RTMIN"`, as their offset can't be part of a static
+    /// string; `Display` includes it.
+    pub fn short_name(&self) -> &'static str {
+        &self.name()[3..]
+    }
+
+    /// Every signal in `ALL` with its number on this platform and its name,
+    /// in order of number, e.g. for printing a `kill -l` style table.
+    pub fn enumerate() -> impl Iterator<Item = (Signal, i32, &'static str)> {
+        Signal::ALL
+            .iter()
+            .map(|sig| (*sig, sig.to_i32(), sig.name()))
+    }
+
+    /// The canonical name of every signal `FromStr` accepts, e.g. for
+    /// command line completion. Other spellings parse too (`"int"`,
+    /// `"INT"`), as do real-time signals past the first (`"SIGRTMIN+3"`),
+    /// which are left out since their range is only known at runtime.
+    /// `"SIGRTMIN"` is only there on platforms with real-time signals.
+    pub fn names() -> &'static [&'static str] {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let names = &[
+            "SIGHUP", "SIGINT", "SIGQUIT", "SIGILL", "SIGABRT", "SIGFPE", "SIGKILL", "SIGSEGV",
+            "SIGPIPE", "SIGALRM", "SIGTERM", "SIGUSR1", "SIGUSR2", "SIGCHLD", "SIGWINCH",
+            "SIGCONT", "SIGTSTP", "SIGSTOP", "SIGRTMIN",
+        ];
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let names = &[
+            "SIGHUP", "SIGINT", "SIGQUIT", "SIGILL", "SIGABRT", "SIGFPE", "SIGKILL", "SIGSEGV",
+            "SIGPIPE", "SIGALRM", "SIGTERM", "SIGUSR1", "SIGUSR2", "SIGCHLD", "SIGWINCH",
+            "SIGCONT", "SIGTSTP", "SIGSTOP",
+        ];
+        names
+    }
+
+    /// Sends this signal to the current process. It's delivered like any
+    /// other signal, through whatever handler is installed for it: a
+    /// `Signals` subscribed to it will see it, and if nothing handles it the
+    /// default action applies, which may well end the process.
+    ///
+    /// SIGKILL and SIGSTOP are refused with `Uncatchable`, since nothing can
+    /// handle them: raising them would kill or stop the process on the spot.
+    /// On Windows only `Int` and `Term` can be raised, as Ctrl-C and
+    /// Ctrl-Break events sent to the whole console.
+    #[cfg(feature = "std")]
+    pub fn raise(&self) -> Result<(), SignalError> {
+        let sig_num = self.to_i32();
+        match self {
+            Signal::Kill | Signal::Stop => Err(SignalError::Uncatchable(*self)),
+            _ if platform::raise(sig_num) => Ok(()),
+            _ => Err(SignalError::UnsupportedSignal(sig_num)),
+        }
+    }
+
+    /// The signal that killed a child process, if that's how it ended.
+    /// Normal exits give `None`, as do signals with no variant (or, on
+    /// Windows, where processes aren't killed by signals, any status).
+    #[cfg(feature = "std")]
+    pub fn from_exit_status(status: &std::process::ExitStatus) -> Option<Signal> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            status.signal().and_then(|n| Signal::try_from(n).ok())
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = status;
+            None
+        }
+    }
+
+    pub fn default_action(&self) -> DefaultAction {
+        match self {
+            Signal::Hup | Signal::Int | Signal::Kill | Signal::Pipe | Signal::Alrm => {
+                DefaultAction::Term
+            }
+            Signal::Term | Signal::Usr1 | Signal::Usr2 => DefaultAction::Term,
+            Signal::Quit | Signal::Ill | Signal::Abrt | Signal::Fpe | Signal::Segv => {
+                DefaultAction::Core
+            }
+            Signal::Tstp | Signal::Stop => DefaultAction::Stop,
+            Signal::Chld | Signal::Winch => DefaultAction::Ignore,
+            Signal::Cont => DefaultAction::Cont,
+            Signal::Realtime(_) => DefaultAction::Term,
+        }
+    }
+
+    pub fn is_terminating(&self) -> bool {
+        match self {
+            // Requests to shut down, sent by a user, a terminal or a
+            // supervisor: SIGHUP (terminal hangup), SIGINT (Ctrl-C),
+            // SIGQUIT (Ctrl-\), SIGTERM (polite kill) and SIGKILL (which
+            // can't be caught, but is listed for completeness).
+            Signal::Hup | Signal::Int | Signal::Quit | Signal::Term | Signal::Kill => true,
+            // Signals whose default action terminates the process because
+            // something went wrong: a fatal error (SIGILL, SIGFPE, SIGSEGV),
+            // an abort(3), a write to a closed pipe, or an expired alarm.
+            Signal::Ill | Signal::Fpe | Signal::Segv | Signal::Abrt => true,
+            Signal::Pipe | Signal::Alrm => true,
+            // The user-defined signals terminate by default per POSIX, but
+            // programs install handlers for them precisely to use them as
+            // notifications, so they are not treated as shutdown requests.
+            Signal::Usr1 | Signal::Usr2 => false,
+            // Notifications (child status changed, terminal resized, process
+            // continued) and job control (stop requests) never end the
+            // process.
+            Signal::Chld | Signal::Winch | Signal::Cont => false,
+            Signal::Tstp | Signal::Stop => false,
+            // Real-time signals carry application-defined meaning, like the
+            // user-defined signals.
+            Signal::Realtime(_) => false,
+        }
+    }
+
+    /// A short English description of what the signal means, after
+    /// signal(7), e.g. "interrupt from keyboard (Ctrl-C)" for `Int`, for
+    /// messages like "received SIGINT: interrupt from keyboard (Ctrl-C)".
+    pub fn description(&self) -> &'static str {
+        match self {
+            Signal::Hup => "hangup of controlling terminal or death of controlling process",
+            Signal::Int => "interrupt from keyboard (Ctrl-C)",
+            Signal::Quit => "quit from keyboard (Ctrl-\\)",
+            Signal::Ill => "illegal instruction",
+            Signal::Abrt => "abort signal from abort(3)",
+            Signal::Fpe => "erroneous arithmetic operation",
+            Signal::Kill => "kill signal",
+            Signal::Segv => "invalid memory reference",
+            Signal::Pipe => "broken pipe: write to pipe with no readers",
+            Signal::Alrm => "timer signal from alarm(2)",
+            Signal::Term => "termination request",
+            Signal::Usr1 => "user-defined signal 1",
+            Signal::Usr2 => "user-defined signal 2",
+            Signal::Chld => "child stopped or terminated",
+            Signal::Winch => "terminal window resized",
+            Signal::Cont => "continue if stopped",
+            Signal::Tstp => "stop typed at terminal (Ctrl-Z)",
+            Signal::Stop => "stop process",
+            Signal::Realtime(_) => "real-time signal, with an application-defined meaning",
+        }
+    }
+
+    /// The exit code a shell reports for a process killed by this signal,
+    /// 128 plus its number (130 for `Int`), for supervisors that want to
+    /// exit the way their child did. Real-time signals follow the same rule
+    /// with the number they have here, e.g. 162 for SIGRTMIN on Linux; on
+    /// platforms without them, there's no number, and this gives a
+    /// meaningless 127.
+    pub fn exit_code(&self) -> i32 {
+        128 + self.to_i32()
+    }
+
+    /// The signal numbered `sig_num` on Linux, whatever the current
+    /// platform, e.g. to make sense of a number reported by a Linux host;
+    /// `to_i32` then gives the local number. This is the numbering of x86,
+    /// ARM and most other architectures (MIPS, SPARC and Alpha have their
+    /// own). Real-time signals aren't mapped, as their numbers depend on the
+    /// C library.
+    pub fn from_linux_number(sig_num: i32) -> Result<Signal, SignalError> {
+        LINUX_NUMBERS
+            .iter()
+            .find(|(_, n)| *n == sig_num)
+            .map(|(sig, _)| *sig)
+            .ok_or(SignalError::UnsupportedSignal(sig_num))
+    }
+
+    /// Like `from_linux_number`, for macOS numbering, which the BSDs share
+    /// for every signal with a variant.
+    pub fn from_macos_number(sig_num: i32) -> Result<Signal, SignalError> {
+        MACOS_NUMBERS
+            .iter()
+            .find(|(_, n)| *n == sig_num)
+            .map(|(sig, _)| *sig)
+            .ok_or(SignalError::UnsupportedSignal(sig_num))
+    }
+
+    /// This signal's number on Linux (see `from_linux_number`), or `None`
+    /// for real-time signals.
+    pub fn to_linux_number(&self) -> Option<i32> {
+        LINUX_NUMBERS
+            .iter()
+            .find(|(sig, _)| sig == self)
+            .map(|(_, n)| *n)
+    }
+
+    /// This signal's number on macOS, or `None` for real-time signals,
+    /// which macOS doesn't have.
+    pub fn to_macos_number(&self) -> Option<i32> {
+        MACOS_NUMBERS
+            .iter()
+            .find(|(sig, _)| sig == self)
+            .map(|(_, n)| *n)
+    }
+}
+
+// The numbers behind `from_linux_number` and `from_macos_number`. Most agree;
+// the ones that don't are USR1, USR2, CHLD, CONT, TSTP and STOP.
+const LINUX_NUMBERS: &[(Signal, i32)] = &[
+    (Signal::Hup, 1),
+    (Signal::Int, 2),
+    (Signal::Quit, 3),
+    (Signal::Ill, 4),
+    (Signal::Abrt, 6),
+    (Signal::Fpe, 8),
+    (Signal::Kill, 9),
+    (Signal::Usr1, 10),
+    (Signal::Segv, 11),
+    (Signal::Usr2, 12),
+    (Signal::Pipe, 13),
+    (Signal::Alrm, 14),
+    (Signal::Term, 15),
+    (Signal::Chld, 17),
+    (Signal::Cont, 18),
+    (Signal::Stop, 19),
+    (Signal::Tstp, 20),
+    (Signal::Winch, 28),
+];
+
+const MACOS_NUMBERS: &[(Signal, i32)] = &[
+    (Signal::Hup, 1),
+    (Signal::Int, 2),
+    (Signal::Quit, 3),
+    (Signal::Ill, 4),
+    (Signal::Abrt, 6),
+    (Signal::Fpe, 8),
+    (Signal::Kill, 9),
+    (Signal::Segv, 11),
+    (Signal::Pipe, 13),
+    (Signal::Alrm, 14),
+    (Signal::Term, 15),
+    (Signal::Stop, 17),
+    (Signal::Tstp, 18),
+    (Signal::Cont, 19),
+    (Signal::Chld, 20),
+    (Signal::Winch, 28),
+    (Signal::Usr1, 30),
+    (Signal::Usr2, 31),
+];
+
+impl Signal {
+    /// Every signal with a variant of its own, in order of signal number on
+    /// the current platform (which is also their `Ord` order). Unlike
+    /// `all()`, this includes the signals that can't or shouldn't be handled,
+    /// like `Kill` and `Pipe`. Real-time signals aren't included, since their
+    /// range is only known at runtime; see `realtime()`.
+    // The numbering differs between platforms, hence one list per family.
+    #[cfg(not(any(
+        windows,
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        target_os = "solaris",
+        target_os = "illumos",
+        target_arch = "sparc",
+        target_arch = "sparc64",
+        target_arch = "mips",
+        target_arch = "mips64"
+    )))]
+    pub const ALL: &'static [Signal] = &[
+        Signal::Hup,
+        Signal::Int,
+        Signal::Quit,
+        Signal::Ill,
+        Signal::Abrt,
+        Signal::Fpe,
+        Signal::Kill,
+        Signal::Usr1,
+        Signal::Segv,
+        Signal::Usr2,
+        Signal::Pipe,
+        Signal::Alrm,
+        Signal::Term,
+        Signal::Chld,
+        Signal::Cont,
+        Signal::Stop,
+        Signal::Tstp,
+        Signal::Winch,
+    ];
+    #[cfg(windows)]
+    pub const ALL: &'static [Signal] = &[
+        Signal::Hup,
+        Signal::Int,
+        Signal::Quit,
+        Signal::Ill,
+        Signal::Fpe,
+        Signal::Kill,
+        Signal::Usr1,
+        Signal::Segv,
+        Signal::Usr2,
+        Signal::Pipe,
+        Signal::Alrm,
+        Signal::Term,
+        Signal::Chld,
+        Signal::Cont,
+        Signal::Stop,
+        Signal::Tstp,
+        Signal::Abrt,
+        Signal::Winch,
+    ];
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly",
+        all(
+            target_os = "linux",
+            any(target_arch = "sparc", target_arch = "sparc64")
+        )
+    ))]
+    pub const ALL: &'static [Signal] = &[
+        Signal::Hup,
+        Signal::Int,
+        Signal::Quit,
+        Signal::Ill,
+        Signal::Abrt,
+        Signal::Fpe,
+        Signal::Kill,
+        Signal::Segv,
+        Signal::Pipe,
+        Signal::Alrm,
+        Signal::Term,
+        Signal::Stop,
+        Signal::Tstp,
+        Signal::Cont,
+        Signal::Chld,
+        Signal::Winch,
+        Signal::Usr1,
+        Signal::Usr2,
+    ];
+    #[cfg(any(
+        target_os = "solaris",
+        target_os = "illumos",
+        all(
+            any(target_os = "linux", target_os = "android"),
+            any(target_arch = "mips", target_arch = "mips64")
+        )
+    ))]
+    pub const ALL: &'static [Signal] = &[
+        Signal::Hup,
+        Signal::Int,
+        Signal::Quit,
+        Signal::Ill,
+        Signal::Abrt,
+        Signal::Fpe,
+        Signal::Kill,
+        Signal::Segv,
+        Signal::Pipe,
+        Signal::Alrm,
+        Signal::Term,
+        Signal::Usr1,
+        Signal::Usr2,
+        Signal::Chld,
+        Signal::Winch,
+        Signal::Stop,
+        Signal::Tstp,
+        Signal::Cont,
+    ];
+
+    /// One of each variant, in the order they're declared, with
+    /// `Realtime(0)` standing in for all the real-time signals. It's the
+    /// same on every platform, unlike `ALL`. As `Signal` is
+    /// `#[non_exhaustive]`, code outside this crate can't have the compiler
+    /// check that a match covers every variant; a test running every entry
+    /// through that match (and failing on the wildcard arm) catches
+    /// variants added in newer versions instead.
+    pub fn all_variants() -> &'static [Signal] {
+        &[
+            Signal::Hup,
+            Signal::Int,
+            Signal::Quit,
+            Signal::Ill,
+            Signal::Abrt,
+            Signal::Fpe,
+            Signal::Kill,
+            Signal::Segv,
+            Signal::Pipe,
+            Signal::Alrm,
+            Signal::Term,
+            Signal::Usr1,
+            Signal::Usr2,
+            Signal::Chld,
+            Signal::Winch,
+            Signal::Cont,
+            Signal::Tstp,
+            Signal::Stop,
+            Signal::Realtime(0),
+        ]
+    }
+}
+
+impl PartialOrd for Signal {
+    fn partial_cmp(&self, other: &Signal) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Signal {
+    fn cmp(&self, other: &Signal) -> cmp::Ordering {
+        // Real-time signals all have the same (invalid) number on platforms
+        // without them, so their offset breaks the tie.
+        self.to_i32()
+            .cmp(&other.to_i32())
+            .then_with(|| match (self, other) {
+                (Signal::Realtime(a), Signal::Realtime(b)) => a.cmp(b),
+                _ => cmp::Ordering::Equal,
+            })
+    }
+}
+
+/// What the OS does with a signal when nobody handles it, as described in
+/// `signal(7)`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DefaultAction {
+    /// Terminate the process.
+    Term,
+    /// Terminate the process and dump core.
+    Core,
+    /// Stop (suspend) the process.
+    Stop,
+    /// Ignore the signal.
+    Ignore,
+    /// Continue the process if it is stopped.
+    Cont,
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Signal::Realtime(offset) if *offset > 0 => write!(f, "SIGRTMIN+{}", offset),
+            sig => f.write_str(sig.name()),
+        }
+    }
+}
+
+/// Formats as `Signal::Variant(NAME, number)`, e.g. `Signal::Int(SIGINT, 2)`
+/// or `Signal::Realtime(SIGRTMIN+3, 37)`, where the name is the `Display` one
+/// and the number is `to_i32()` on the current platform. This format is
+/// stable, so it's safe to match on in logs and tests.
+impl fmt::Debug for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let variant = match self {
+            Signal::Hup => "Hup",
+            Signal::Int => "Int",
+            Signal::Quit => "Quit",
+            Signal::Ill => "Ill",
+            Signal::Abrt => "Abrt",
+            Signal::Fpe => "Fpe",
+            Signal::Kill => "Kill",
+            Signal::Segv => "Segv",
+            Signal::Pipe => "Pipe",
+            Signal::Alrm => "Alrm",
+            Signal::Term => "Term",
+            Signal::Usr1 => "Usr1",
+            Signal::Usr2 => "Usr2",
+            Signal::Chld => "Chld",
+            Signal::Winch => "Winch",
+            Signal::Cont => "Cont",
+            Signal::Tstp => "Tstp",
+            Signal::Stop => "Stop",
+            Signal::Realtime(_) => "Realtime",
+        };
+        write!(f, "Signal::{}({}, {})", variant, self, self.to_i32())
+    }
+}
+
+// simple_signal models a subset of our signals, so converting from it always
+// works, while the other way round fails for the signals it lacks.
+#[cfg(all(unix, feature = "simple-signal"))]
+impl From<simple_signal::Signal> for Signal {
+    fn from(sig: simple_signal::Signal) -> Signal {
+        match sig {
+            simple_signal::Signal::Hup => Signal::Hup,
+            simple_signal::Signal::Int => Signal::Int,
+            simple_signal::Signal::Quit => Signal::Quit,
+            simple_signal::Signal::Ill => Signal::Ill,
+            simple_signal::Signal::Abrt => Signal::Abrt,
+            simple_signal::Signal::Fpe => Signal::Fpe,
+            simple_signal::Signal::Kill => Signal::Kill,
+            simple_signal::Signal::Segv => Signal::Segv,
+            simple_signal::Signal::Pipe => Signal::Pipe,
+            simple_signal::Signal::Alrm => Signal::Alrm,
+            simple_signal::Signal::Term => Signal::Term,
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "simple-signal"))]
+impl TryFrom<Signal> for simple_signal::Signal {
+    type Error = SignalError;
+
+    fn try_from(sig: Signal) -> Result<simple_signal::Signal, SignalError> {
+        match sig {
+            Signal::Hup => Ok(simple_signal::Signal::Hup),
+            Signal::Int => Ok(simple_signal::Signal::Int),
+            Signal::Quit => Ok(simple_signal::Signal::Quit),
+            Signal::Ill => Ok(simple_signal::Signal::Ill),
+            Signal::Abrt => Ok(simple_signal::Signal::Abrt),
+            Signal::Fpe => Ok(simple_signal::Signal::Fpe),
+            Signal::Kill => Ok(simple_signal::Signal::Kill),
+            Signal::Segv => Ok(simple_signal::Signal::Segv),
+            Signal::Pipe => Ok(simple_signal::Signal::Pipe),
+            Signal::Alrm => Ok(simple_signal::Signal::Alrm),
+            Signal::Term => Ok(simple_signal::Signal::Term),
+            _ => Err(SignalError::UnsupportedSignal(sig.to_i32())),
+        }
+    }
+}
+
+// Real-time signals have no `nix` variant, and `nix` has variants for
+// signals without one here (e.g. SIGBUS), so neither direction always works.
+#[cfg(all(unix, feature = "nix"))]
+impl TryFrom<nix::sys::signal::Signal> for Signal {
+    type Error = SignalError;
+
+    fn try_from(sig: nix::sys::signal::Signal) -> Result<Signal, SignalError> {
+        use nix::sys::signal::Signal as Nix;
+        match sig {
+            Nix::SIGHUP => Ok(Signal::Hup),
+            Nix::SIGINT => Ok(Signal::Int),
+            Nix::SIGQUIT => Ok(Signal::Quit),
+            Nix::SIGILL => Ok(Signal::Ill),
+            Nix::SIGABRT => Ok(Signal::Abrt),
+            Nix::SIGFPE => Ok(Signal::Fpe),
+            Nix::SIGKILL => Ok(Signal::Kill),
+            Nix::SIGSEGV => Ok(Signal::Segv),
+            Nix::SIGPIPE => Ok(Signal::Pipe),
+            Nix::SIGALRM => Ok(Signal::Alrm),
+            Nix::SIGTERM => Ok(Signal::Term),
+            Nix::SIGUSR1 => Ok(Signal::Usr1),
+            Nix::SIGUSR2 => Ok(Signal::Usr2),
+            Nix::SIGCHLD => Ok(Signal::Chld),
+            Nix::SIGWINCH => Ok(Signal::Winch),
+            Nix::SIGCONT => Ok(Signal::Cont),
+            Nix::SIGTSTP => Ok(Signal::Tstp),
+            Nix::SIGSTOP => Ok(Signal::Stop),
+            _ => Err(SignalError::UnsupportedSignal(sig as i32)),
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "nix"))]
+impl TryFrom<Signal> for nix::sys::signal::Signal {
+    type Error = SignalError;
+
+    fn try_from(sig: Signal) -> Result<nix::sys::signal::Signal, SignalError> {
+        use nix::sys::signal::Signal as Nix;
+        match sig {
+            Signal::Hup => Ok(Nix::SIGHUP),
+            Signal::Int => Ok(Nix::SIGINT),
+            Signal::Quit => Ok(Nix::SIGQUIT),
+            Signal::Ill => Ok(Nix::SIGILL),
+            Signal::Abrt => Ok(Nix::SIGABRT),
+            Signal::Fpe => Ok(Nix::SIGFPE),
+            Signal::Kill => Ok(Nix::SIGKILL),
+            Signal::Segv => Ok(Nix::SIGSEGV),
+            Signal::Pipe => Ok(Nix::SIGPIPE),
+            Signal::Alrm => Ok(Nix::SIGALRM),
+            Signal::Term => Ok(Nix::SIGTERM),
+            Signal::Usr1 => Ok(Nix::SIGUSR1),
+            Signal::Usr2 => Ok(Nix::SIGUSR2),
+            Signal::Chld => Ok(Nix::SIGCHLD),
+            Signal::Winch => Ok(Nix::SIGWINCH),
+            Signal::Cont => Ok(Nix::SIGCONT),
+            Signal::Tstp => Ok(Nix::SIGTSTP),
+            Signal::Stop => Ok(Nix::SIGSTOP),
+            Signal::Realtime(_) => Err(SignalError::UnsupportedSignal(sig.to_i32())),
+        }
+    }
+}
+
+// Signals (de)serialize as their `Display` name and accept anything
+// `FromStr` does, e.g. `"SIGTERM"` or `"term"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Signal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Signal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Signal, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Signal {
+    type Err = SignalError;
+
+    fn from_str(s: &str) -> Result<Signal, SignalError> {
+        let upper = s.to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        match name {
+            "HUP" => Ok(Signal::Hup),
+            "INT" => Ok(Signal::Int),
+            "QUIT" => Ok(Signal::Quit),
+            "ILL" => Ok(Signal::Ill),
+            "ABRT" => Ok(Signal::Abrt),
+            "FPE" => Ok(Signal::Fpe),
+            "KILL" => Ok(Signal::Kill),
+            "SEGV" => Ok(Signal::Segv),
+            "PIPE" => Ok(Signal::Pipe),
+            "ALRM" => Ok(Signal::Alrm),
+            "TERM" => Ok(Signal::Term),
+            "USR1" => Ok(Signal::Usr1),
+            "USR2" => Ok(Signal::Usr2),
+            "CHLD" => Ok(Signal::Chld),
+            "WINCH" => Ok(Signal::Winch),
+            "CONT" => Ok(Signal::Cont),
+            "TSTP" => Ok(Signal::Tstp),
+            "STOP" => Ok(Signal::Stop),
+            // Only real-time signals this platform has are accepted, so an
+            // offset past SIGRTMAX never makes it into a `Signal`.
+            _ => match name {
+                "RTMIN" => Some(0),
+                _ => name
+                    .strip_prefix("RTMIN+")
+                    .and_then(|offset| offset.parse().ok()),
+            }
+            .map(Signal::Realtime)
+            .filter(|sig| is_supported(sig.to_i32()))
+            .ok_or_else(|| SignalError::UnsupportedSignalName(s.to_string())),
+        }
+    }
+}
+
+/// Every signal that can be caught, minus SIGPIPE, SIGTSTP and SIGCHLD.
+/// SIGKILL and SIGSTOP can't be caught, so they are left out even though
+/// `Signal` has variants for them. The others are left out on purpose, since
+/// catching them changes how a program behaves by default: SIGPIPE's default
+/// action is what makes `mytool | head` exit quietly once `head` is done, and
+/// catching it would leave such programs writing to (or hanging on) a closed
+/// pipe; catching SIGTSTP means Ctrl-Z no longer suspends the program; and
+/// SIGCHLD arrives whenever a child exits, which only code that manages
+/// children cares about. Pass them to `Signals::with_signals` to receive them
+/// anyway.
+pub fn all() -> Vec<Signal> {
+    vec![
+        Signal::Hup,
+        Signal::Int,
+        Signal::Ill,
+        Signal::Abrt,
+        Signal::Fpe,
+        Signal::Alrm,
+        Signal::Term,
+        Signal::Usr1,
+        Signal::Usr2,
+        Signal::Winch,
+        Signal::Cont,
+    ]
+}
+
+/// Every real-time signal this platform supports, SIGRTMIN to SIGRTMAX.
+/// These aren't part of `all()`; pass them to `Signals::with_signals` to
+/// handle them. Empty on platforms without real-time signals.
+pub fn realtime() -> Vec<Signal> {
+    match numbers::realtime_range() {
+        Some((min, max)) => (0..=max - min)
+            .filter_map(|offset| u8::try_from(offset).ok())
+            .map(Signal::Realtime)
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+impl TryFrom<i32> for Signal {
+    type Error = SignalError;
+
+    fn try_from(sig_num: i32) -> Result<Signal, SignalError> {
+        match sig_num {
+            SIGHUP => Ok(Signal::Hup),
+            SIGINT => Ok(Signal::Int),
+            SIGQUIT => Ok(Signal::Quit),
+            SIGILL => Ok(Signal::Ill),
+            SIGABRT => Ok(Signal::Abrt),
+            SIGFPE => Ok(Signal::Fpe),
+            SIGKILL => Ok(Signal::Kill),
+            SIGSEGV => Ok(Signal::Segv),
+            SIGPIPE => Ok(Signal::Pipe),
+            SIGALRM => Ok(Signal::Alrm),
+            SIGTERM => Ok(Signal::Term),
+            SIGUSR1 => Ok(Signal::Usr1),
+            SIGUSR2 => Ok(Signal::Usr2),
+            SIGCHLD => Ok(Signal::Chld),
+            SIGWINCH => Ok(Signal::Winch),
+            SIGCONT => Ok(Signal::Cont),
+            SIGTSTP => Ok(Signal::Tstp),
+            SIGSTOP => Ok(Signal::Stop),
+            _ => match numbers::realtime_range() {
+                Some((min, max)) if (min..=max).contains(&sig_num) => {
+                    Ok(Signal::Realtime((sig_num - min) as u8))
+                }
+                _ => Err(SignalError::UnsupportedSignal(sig_num)),
+            },
+        }
+    }
+}
+
+impl From<Signal> for i32 {
+    fn from(sig: Signal) -> i32 {
+        sig.to_i32()
+    }
+}
+
+/// The signal with the POSIX number `sig_num` on this platform (SIGINT is
+/// 2, SIGTERM is 15, and so on), like `Signal::try_from`.
+pub fn from_posix_signum(sig_num: i32) -> Result<Signal, SignalError> {
+    Signal::try_from(sig_num)
+}
+
+/// The signal at position `sig_num` in the numbering this crate used up to
+/// 0.2.1, which wasn't the one the OS uses: 0 is `Hup`, 1 `Int`, 3 `Ill`, 4
+/// `Abrt`, 5 `Fpe`, 8 `Pipe`, 9 `Alrm` and 10 `Term`, and nothing else is
+/// mapped.
+///
+/// This is kept working as it always did, so that code that stored or
+/// exchanged those numbers doesn't silently start getting other signals.
+/// It's deprecated as of 0.3 and will be removed in 0.4; use
+/// `from_posix_signum` for real signal numbers, such as the ones the
+/// `mpsc::Sender<i32>` legacy API sends.
+#[deprecated(
+    since = "0.3.0",
+    note = "uses the old, non-POSIX numbering; use `from_posix_signum` instead"
+)]
+pub fn from_i32(sig_num: i32) -> Result<Signal, SignalError> {
+    match sig_num {
+        0 => Ok(Signal::Hup),
+        1 => Ok(Signal::Int),
+        3 => Ok(Signal::Ill),
+        4 => Ok(Signal::Abrt),
+        5 => Ok(Signal::Fpe),
+        8 => Ok(Signal::Pipe),
+        9 => Ok(Signal::Alrm),
+        10 => Ok(Signal::Term),
+        _ => Err(SignalError::UnsupportedSignal(sig_num)),
+    }
+}
+
+/// Whether `from_posix_signum(sig_num)` would succeed. Neither allocates,
+/// so this is just the cheaper thing to write when the `Signal` itself
+/// isn't needed.
+pub fn is_supported(sig_num: i32) -> bool {
+    Signal::try_from(sig_num).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_round_trip() {
+        for sig in Signal::ALL.iter().chain(&realtime()) {
+            assert_eq!(from_posix_signum(sig.to_i32()), Ok(*sig));
+            assert_eq!(i32::from(*sig), sig.to_i32());
+        }
+    }
+
+    // Written out rather than taken from libc, which is where the
+    // implementation gets them.
+    #[cfg(any(
+        not(any(unix, windows)),
+        all(
+            target_os = "linux",
+            not(any(
+                target_arch = "mips",
+                target_arch = "mips64",
+                target_arch = "sparc",
+                target_arch = "sparc64"
+            ))
+        )
+    ))]
+    const NUMBERS: [(Signal, i32); 18] = [
+        (Signal::Hup, 1),
+        (Signal::Int, 2),
+        (Signal::Quit, 3),
+        (Signal::Ill, 4),
+        (Signal::Abrt, 6),
+        (Signal::Fpe, 8),
+        (Signal::Kill, 9),
+        (Signal::Usr1, 10),
+        (Signal::Segv, 11),
+        (Signal::Usr2, 12),
+        (Signal::Pipe, 13),
+        (Signal::Alrm, 14),
+        (Signal::Term, 15),
+        (Signal::Chld, 17),
+        (Signal::Cont, 18),
+        (Signal::Stop, 19),
+        (Signal::Tstp, 20),
+        (Signal::Winch, 28),
+    ];
+    #[cfg(target_os = "macos")]
+    const NUMBERS: [(Signal, i32); 18] = [
+        (Signal::Hup, 1),
+        (Signal::Int, 2),
+        (Signal::Quit, 3),
+        (Signal::Ill, 4),
+        (Signal::Abrt, 6),
+        (Signal::Fpe, 8),
+        (Signal::Kill, 9),
+        (Signal::Segv, 11),
+        (Signal::Pipe, 13),
+        (Signal::Alrm, 14),
+        (Signal::Term, 15),
+        (Signal::Stop, 17),
+        (Signal::Tstp, 18),
+        (Signal::Cont, 19),
+        (Signal::Chld, 20),
+        (Signal::Winch, 28),
+        (Signal::Usr1, 30),
+        (Signal::Usr2, 31),
+    ];
+    #[cfg(windows)]
+    const NUMBERS: [(Signal, i32); 18] = [
+        (Signal::Hup, 1),
+        (Signal::Int, 2),
+        (Signal::Quit, 3),
+        (Signal::Ill, 4),
+        (Signal::Fpe, 8),
+        (Signal::Kill, 9),
+        (Signal::Usr1, 10),
+        (Signal::Segv, 11),
+        (Signal::Usr2, 12),
+        (Signal::Pipe, 13),
+        (Signal::Alrm, 14),
+        (Signal::Term, 15),
+        (Signal::Chld, 17),
+        (Signal::Cont, 18),
+        (Signal::Stop, 19),
+        (Signal::Tstp, 20),
+        (Signal::Abrt, 22),
+        (Signal::Winch, 28),
+    ];
+
+    #[cfg(any(
+        not(unix),
+        target_os = "macos",
+        all(
+            target_os = "linux",
+            not(any(
+                target_arch = "mips",
+                target_arch = "mips64",
+                target_arch = "sparc",
+                target_arch = "sparc64"
+            ))
+        )
+    ))]
+    #[test]
+    fn numbers_are_the_platform_ones() {
+        for (sig, number) in NUMBERS {
+            assert_eq!(sig.to_i32(), number, "{}", sig);
+            assert_eq!(from_posix_signum(number), Ok(sig));
+        }
+        // In the order `ALL` promises.
+        let listed: Vec<Signal> = NUMBERS.iter().map(|(sig, _)| *sig).collect();
+        assert_eq!(listed, Signal::ALL);
+    }
+
+    #[test]
+    fn unknown_numbers_are_rejected() {
+        for number in [0, -1, 1000] {
+            assert_eq!(
+                from_posix_signum(number),
+                Err(SignalError::UnsupportedSignal(number))
+            );
+        }
+    }
+
+    #[test]
+    fn display_uses_the_sig_names() {
+        let expected = [
+            (Signal::Hup, "SIGHUP"),
+            (Signal::Int, "SIGINT"),
+            (Signal::Quit, "SIGQUIT"),
+            (Signal::Ill, "SIGILL"),
+            (Signal::Abrt, "SIGABRT"),
+            (Signal::Fpe, "SIGFPE"),
+            (Signal::Kill, "SIGKILL"),
+            (Signal::Segv, "SIGSEGV"),
+            (Signal::Pipe, "SIGPIPE"),
+            (Signal::Alrm, "SIGALRM"),
+            (Signal::Term, "SIGTERM"),
+            (Signal::Usr1, "SIGUSR1"),
+            (Signal::Usr2, "SIGUSR2"),
+            (Signal::Chld, "SIGCHLD"),
+            (Signal::Winch, "SIGWINCH"),
+            (Signal::Cont, "SIGCONT"),
+            (Signal::Tstp, "SIGTSTP"),
+            (Signal::Stop, "SIGSTOP"),
+            (Signal::Realtime(0), "SIGRTMIN"),
+            (Signal::Realtime(3), "SIGRTMIN+3"),
+        ];
+        for (sig, name) in expected {
+            assert_eq!(format!("{}", sig), name);
+        }
+    }
+
+    #[test]
+    fn terminating_signals() {
+        let expected = [
+            (Signal::Hup, true),
+            (Signal::Int, true),
+            (Signal::Quit, true),
+            (Signal::Ill, true),
+            (Signal::Abrt, true),
+            (Signal::Fpe, true),
+            (Signal::Kill, true),
+            (Signal::Segv, true),
+            (Signal::Pipe, true),
+            (Signal::Alrm, true),
+            (Signal::Term, true),
+            (Signal::Usr1, false),
+            (Signal::Usr2, false),
+            (Signal::Chld, false),
+            (Signal::Winch, false),
+            (Signal::Cont, false),
+            (Signal::Tstp, false),
+            (Signal::Stop, false),
+            (Signal::Realtime(0), false),
+        ];
+        for (sig, terminating) in expected {
+            assert_eq!(sig.is_terminating(), terminating, "{}", sig);
+        }
+    }
+
+    #[test]
+    fn names_parse() {
+        assert_eq!("sigterm".parse::<Signal>(), Ok(Signal::Term));
+        assert_eq!("TERM".parse::<Signal>(), Ok(Signal::Term));
+        assert_eq!("SIGINT".parse::<Signal>(), Ok(Signal::Int));
+        assert_eq!(
+            "FOO".parse::<Signal>(),
+            Err(SignalError::UnsupportedSignalName("FOO".to_string()))
+        );
+    }
+
+    #[test]
+    fn display_names_parse_back() {
+        for sig in Signal::ALL {
+            assert_eq!(sig.to_string().parse::<Signal>(), Ok(*sig));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips_through_the_names() {
+        for sig in Signal::ALL {
+            let json = serde_json::to_string(sig).unwrap();
+            assert_eq!(json, format!("\"{}\"", sig));
+            assert_eq!(serde_json::from_str::<Signal>(&json).unwrap(), *sig);
+        }
+        assert_eq!(
+            serde_json::from_str::<Signal>("\"term\"").unwrap(),
+            Signal::Term
+        );
+        let err = serde_json::from_str::<Signal>("\"SIGFOO\"").unwrap_err();
+        assert!(err.to_string().contains("SIGFOO"), "{}", err);
+    }
+
+    #[test]
+    fn job_control_names_parse() {
+        let expected = [
+            ("SIGCHLD", Signal::Chld),
+            ("winch", Signal::Winch),
+            ("SigCont", Signal::Cont),
+            ("TSTP", Signal::Tstp),
+            ("sigstop", Signal::Stop),
+        ];
+        for (name, sig) in expected {
+            assert_eq!(name.parse::<Signal>(), Ok(sig), "{}", name);
+        }
+    }
+
+    #[test]
+    fn default_actions_follow_signal_7() {
+        let expected = [
+            (Signal::Hup, DefaultAction::Term),
+            (Signal::Int, DefaultAction::Term),
+            (Signal::Quit, DefaultAction::Core),
+            (Signal::Ill, DefaultAction::Core),
+            (Signal::Abrt, DefaultAction::Core),
+            (Signal::Fpe, DefaultAction::Core),
+            (Signal::Kill, DefaultAction::Term),
+            (Signal::Segv, DefaultAction::Core),
+            (Signal::Pipe, DefaultAction::Term),
+            (Signal::Alrm, DefaultAction::Term),
+            (Signal::Term, DefaultAction::Term),
+            (Signal::Usr1, DefaultAction::Term),
+            (Signal::Usr2, DefaultAction::Term),
+            (Signal::Chld, DefaultAction::Ignore),
+            (Signal::Winch, DefaultAction::Ignore),
+            (Signal::Cont, DefaultAction::Cont),
+            (Signal::Tstp, DefaultAction::Stop),
+            (Signal::Stop, DefaultAction::Stop),
+            (Signal::Realtime(0), DefaultAction::Term),
+        ];
+        for (sig, action) in expected {
+            assert_eq!(sig.default_action(), action, "{}", sig);
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn realtime_signals_stop_at_sigrtmax() {
+        let (min, max) = (libc::SIGRTMIN(), libc::SIGRTMAX());
+        let last = u8::try_from(max - min).unwrap();
+        assert_eq!(from_posix_signum(min), Ok(Signal::Realtime(0)));
+        assert_eq!(from_posix_signum(max), Ok(Signal::Realtime(last)));
+        assert_eq!(
+            from_posix_signum(max + 1),
+            Err(SignalError::UnsupportedSignal(max + 1))
+        );
+        assert_eq!(realtime().first(), Some(&Signal::Realtime(0)));
+        assert_eq!(realtime().last(), Some(&Signal::Realtime(last)));
+        assert!(is_supported(Signal::Realtime(last).to_i32()));
+        assert!(!is_supported(Signal::Realtime(last + 1).to_i32()));
+        let name = format!("SIGRTMIN+{}", last);
+        assert_eq!(name.parse::<Signal>(), Ok(Signal::Realtime(last)));
+        let name = format!("SIGRTMIN+{}", last + 1);
+        assert_eq!(
+            name.parse::<Signal>(),
+            Err(SignalError::UnsupportedSignalName(name))
+        );
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    #[test]
+    fn realtime_signals_are_rejected_without_os_support() {
+        assert!(realtime().is_empty());
+        assert!(!is_supported(Signal::Realtime(0).to_i32()));
+        assert!("SIGRTMIN".parse::<Signal>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn conversions_use_the_posix_numbers() {
+        assert_eq!(Signal::try_from(libc::SIGINT), Ok(Signal::Int));
+        assert_eq!(Signal::try_from(libc::SIGTERM), Ok(Signal::Term));
+        assert_eq!(Signal::try_from(libc::SIGUSR1), Ok(Signal::Usr1));
+        assert_eq!(i32::from(Signal::Hup), libc::SIGHUP);
+        assert_eq!(i32::from(Signal::Usr2), libc::SIGUSR2);
+        assert_eq!(Signal::try_from(0), Err(SignalError::UnsupportedSignal(0)));
+        assert_eq!(
+            from_posix_signum(libc::SIGINT),
+            Signal::try_from(libc::SIGINT)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn all_variants_collect_into_a_set() {
+        use std::collections::HashSet;
+
+        let set: HashSet<Signal> = Signal::ALL.iter().copied().collect();
+        assert_eq!(set.len(), Signal::ALL.len());
+        assert!(set.contains(&Signal::Term));
+        assert!(set.contains(&Signal::Winch));
+        assert!(!set.contains(&Signal::Realtime(1)));
+        let mut sorted = Signal::ALL.to_vec();
+        sorted.sort();
+        assert_eq!(sorted.first(), Some(&Signal::Hup));
+    }
+
+    #[cfg(all(unix, feature = "std"))]
+    #[test]
+    fn exit_statuses_tell_the_signal() {
+        use std::process::Command;
+
+        let killed = Command::new("sh")
+            .args(["-c", "kill -TERM $$"])
+            .status()
+            .unwrap();
+        assert_eq!(Signal::from_exit_status(&killed), Some(Signal::Term));
+        let exited = Command::new("sh").args(["-c", "exit 3"]).status().unwrap();
+        assert_eq!(Signal::from_exit_status(&exited), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn debug_output_is_pinned() {
+        assert_eq!(format!("{:?}", Signal::Int), "Signal::Int(SIGINT, 2)");
+        assert_eq!(format!("{:?}", Signal::Term), "Signal::Term(SIGTERM, 15)");
+        assert_eq!(format!("{:?}", Signal::Kill), "Signal::Kill(SIGKILL, 9)");
+        assert_eq!(
+            format!("{:?}", Signal::Usr1),
+            format!("Signal::Usr1(SIGUSR1, {})", SIGUSR1)
+        );
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(
+            format!("{:?}", Signal::Realtime(3)),
+            format!("Signal::Realtime(SIGRTMIN+3, {})", libc::SIGRTMIN() + 3)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn every_listed_name_parses_back() {
+        for name in Signal::names() {
+            let sig: Signal = name.parse().unwrap();
+            assert_eq!(sig.to_string(), *name);
+        }
+    }
+
+    #[test]
+    fn supported_numbers() {
+        assert!(is_supported(SIGHUP));
+        assert!(is_supported(SIGTERM));
+        assert!(is_supported(SIGWINCH));
+        for number in [0, -1, i32::MIN, i32::MAX] {
+            assert!(!is_supported(number), "{}", number);
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            assert!(is_supported(libc::SIGRTMIN()));
+            assert!(is_supported(libc::SIGRTMAX()));
+            assert!(!is_supported(libc::SIGRTMAX() + 1));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn enumerate_lists_every_variant_once() {
+        let listed: Vec<(Signal, i32, &str)> = Signal::enumerate().collect();
+        assert_eq!(listed.len(), Signal::ALL.len());
+        let mut numbers: Vec<i32> = listed.iter().map(|(_, n, _)| *n).collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+        assert_eq!(numbers.len(), listed.len());
+        for (sig, number, name) in listed {
+            assert_eq!(sig.to_i32(), number);
+            assert_eq!(sig.to_string(), name);
+        }
+    }
+
+    #[test]
+    fn short_names_drop_the_prefix() {
+        let expected = [
+            (Signal::Hup, "HUP"),
+            (Signal::Int, "INT"),
+            (Signal::Quit, "QUIT"),
+            (Signal::Ill, "ILL"),
+            (Signal::Abrt, "ABRT"),
+            (Signal::Fpe, "FPE"),
+            (Signal::Kill, "KILL"),
+            (Signal::Segv, "SEGV"),
+            (Signal::Pipe, "PIPE"),
+            (Signal::Alrm, "ALRM"),
+            (Signal::Term, "TERM"),
+            (Signal::Usr1, "USR1"),
+            (Signal::Usr2, "USR2"),
+            (Signal::Chld, "CHLD"),
+            (Signal::Winch, "WINCH"),
+            (Signal::Cont, "CONT"),
+            (Signal::Tstp, "TSTP"),
+            (Signal::Stop, "STOP"),
+            (Signal::Realtime(2), "RTMIN"),
+        ];
+        for (sig, name) in expected {
+            assert_eq!(sig.short_name(), name);
+        }
+        for sig in Signal::ALL {
+            assert_eq!(sig.short_name().parse::<Signal>(), Ok(*sig));
+            assert_eq!(sig.to_string().parse::<Signal>(), Ok(*sig));
+        }
+    }
+
+    #[cfg(all(unix, feature = "simple-signal"))]
+    #[test]
+    fn simple_signal_conversions() {
+        let mappable = [
+            (simple_signal::Signal::Hup, Signal::Hup),
+            (simple_signal::Signal::Int, Signal::Int),
+            (simple_signal::Signal::Quit, Signal::Quit),
+            (simple_signal::Signal::Ill, Signal::Ill),
+            (simple_signal::Signal::Abrt, Signal::Abrt),
+            (simple_signal::Signal::Fpe, Signal::Fpe),
+            (simple_signal::Signal::Kill, Signal::Kill),
+            (simple_signal::Signal::Segv, Signal::Segv),
+            (simple_signal::Signal::Pipe, Signal::Pipe),
+            (simple_signal::Signal::Alrm, Signal::Alrm),
+            (simple_signal::Signal::Term, Signal::Term),
+        ];
+        for (theirs, ours) in mappable {
+            assert_eq!(Signal::from(theirs), ours);
+            assert_eq!(simple_signal::Signal::try_from(ours), Ok(theirs));
+        }
+        for sig in [Signal::Usr1, Signal::Winch, Signal::Realtime(0)] {
+            assert_eq!(
+                simple_signal::Signal::try_from(sig),
+                Err(SignalError::UnsupportedSignal(sig.to_i32()))
+            );
+        }
+    }
+
+    #[test]
+    fn numbers_translate_between_linux_and_macos() {
+        assert_eq!(Signal::from_linux_number(10), Ok(Signal::Usr1));
+        assert_eq!(Signal::from_macos_number(30), Ok(Signal::Usr1));
+        assert_eq!(Signal::Usr1.to_linux_number(), Some(10));
+        assert_eq!(Signal::Usr1.to_macos_number(), Some(30));
+        // SIGUSR1 on Linux is SIGBUS on macOS, which has no variant.
+        assert_eq!(
+            Signal::from_macos_number(10),
+            Err(SignalError::UnsupportedSignal(10))
+        );
+        assert_eq!(Signal::from_linux_number(20), Ok(Signal::Tstp));
+        assert_eq!(Signal::from_macos_number(20), Ok(Signal::Chld));
+        assert_eq!(Signal::Realtime(0).to_linux_number(), None);
+        for sig in Signal::ALL {
+            let linux = sig.to_linux_number().unwrap();
+            let macos = sig.to_macos_number().unwrap();
+            assert_eq!(Signal::from_linux_number(linux), Ok(*sig));
+            assert_eq!(Signal::from_macos_number(macos), Ok(*sig));
+        }
+        #[cfg(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        assert_eq!(Signal::Usr1.to_linux_number(), Some(Signal::Usr1.to_i32()));
+    }
+
+    #[cfg(all(unix, feature = "nix"))]
+    #[test]
+    fn nix_conversions() {
+        use nix::sys::signal::Signal as Nix;
+
+        for sig in Signal::ALL {
+            let theirs = Nix::try_from(*sig).unwrap();
+            assert_eq!(theirs as i32, sig.to_i32());
+            assert_eq!(Signal::try_from(theirs), Ok(*sig));
+        }
+        assert_eq!(
+            Signal::try_from(Nix::SIGBUS),
+            Err(SignalError::UnsupportedSignal(libc::SIGBUS))
+        );
+        assert_eq!(
+            Nix::try_from(Signal::Realtime(0)),
+            Err(SignalError::UnsupportedSignal(Signal::Realtime(0).to_i32()))
+        );
+    }
+
+    #[test]
+    fn exit_codes_follow_the_shell_convention() {
+        assert_eq!(Signal::Int.exit_code(), 130);
+        assert_eq!(Signal::Kill.exit_code(), 137);
+        assert_eq!(Signal::Term.exit_code(), 143);
+        for sig in Signal::ALL {
+            assert_eq!(sig.exit_code(), 128 + sig.to_i32());
+        }
+    }
+
+    #[test]
+    fn every_variant_has_a_description() {
+        for sig in Signal::all_variants() {
+            assert!(!sig.description().is_empty(), "{}", sig);
+        }
+        assert_eq!(
+            Signal::Int.description(),
+            "interrupt from keyboard (Ctrl-C)"
+        );
+    }
+
+    #[test]
+    fn c_names_match_the_display_names() {
+        assert_eq!(Signal::Int.c_name().to_str().unwrap(), "SIGINT");
+        assert_eq!(Signal::Term.c_name().to_str().unwrap(), "SIGTERM");
+        assert_eq!(Signal::Realtime(4).c_name().to_str().unwrap(), "SIGRTMIN");
+        for sig in Signal::ALL {
+            assert_eq!(sig.c_name().to_bytes(), sig.name().as_bytes());
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn from_i32_keeps_the_old_numbering() {
+        let old = [
+            (0, Signal::Hup),
+            (1, Signal::Int),
+            (3, Signal::Ill),
+            (4, Signal::Abrt),
+            (5, Signal::Fpe),
+            (8, Signal::Pipe),
+            (9, Signal::Alrm),
+            (10, Signal::Term),
+        ];
+        for (n, sig) in old.iter() {
+            assert_eq!(from_i32(*n), Ok(*sig));
+        }
+        for n in [-1, 2, 6, 7, 11, 15, 64].iter() {
+            assert_eq!(from_i32(*n), Err(SignalError::UnsupportedSignal(*n)));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_posix_signum_uses_the_os_numbering() {
+        assert_eq!(from_posix_signum(libc::SIGHUP), Ok(Signal::Hup));
+        assert_eq!(from_posix_signum(libc::SIGTERM), Ok(Signal::Term));
+        assert_eq!(from_posix_signum(libc::SIGUSR1), Ok(Signal::Usr1));
+        assert_eq!(from_posix_signum(0), Err(SignalError::UnsupportedSignal(0)));
+    }
+
+    #[test]
+    fn all_is_in_signal_number_order() {
+        for pair in Signal::ALL.windows(2) {
+            assert!(pair[0].to_i32() < pair[1].to_i32(), "{:?}", pair);
+        }
+    }
+}
@@ -0,0 +1,2644 @@
+use crate::channel::{self, CancelHandle, OverflowPolicy};
+use crate::numbers::{SIGINT, SIGKILL, SIGSTOP};
+use crate::{all, from_posix_signum, platform, DefaultAction, Receiver, Signal, SignalError};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+#[cfg(unix)]
+use std::marker::PhantomData;
+use std::ops::ControlFlow;
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A signal as delivered by the OS: its number, plus the matching `Signal`
+/// if the enum models it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawSignal {
+    pub signal: Option<Signal>,
+    pub number: i32,
+}
+
+impl RawSignal {
+    fn new(number: i32) -> RawSignal {
+        RawSignal {
+            signal: from_posix_signum(number).ok(),
+            number,
+        }
+    }
+}
+
+/// A signal along with who sent it, as received by `subscribe_info`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SignalInfo {
+    pub signal: Signal,
+    /// The pid of the sending process (for SIGCHLD, of the child), or `None`
+    /// when the OS doesn't say: e.g. for signals the kernel raised itself,
+    /// like SIGSEGV, and always on Windows.
+    pub sender_pid: Option<i32>,
+    /// The real uid of the sending process, under the same conditions.
+    pub sender_uid: Option<u32>,
+}
+
+/// A signal along with its classification, as received by
+/// `subscribe_classified`: `action` and `terminating` are what
+/// `Signal::default_action` and `Signal::is_terminating` say.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClassifiedSignal {
+    pub signal: Signal,
+    pub action: DefaultAction,
+    pub terminating: bool,
+}
+
+enum Sink {
+    // The second field is the subscriber's filter; `None` takes everything.
+    Std(channel::Sender<Signal>, Option<Vec<Signal>>),
+    Raw(channel::Sender<RawSignal>),
+    Info(channel::Sender<SignalInfo>),
+    Classified(channel::Sender<ClassifiedSignal>),
+    #[cfg(feature = "crossbeam")]
+    Crossbeam(crossbeam_channel::Sender<Signal>),
+    #[cfg(feature = "tokio")]
+    Tokio(tokio::sync::mpsc::UnboundedSender<Signal>),
+    #[cfg(feature = "async-std")]
+    AsyncStd(async_std::channel::Sender<Signal>),
+}
+
+impl Sink {
+    // Returns false once the subscriber has gone away. Only raw subscribers
+    // hear about signals the enum doesn't model. `classified` is worked out
+    // once for every subscriber by the fan-out. The number of any signal
+    // the subscriber's channel dropped, for being full or a duplicate, is
+    // added to `dropped`.
+    fn send(
+        &self,
+        raw: RawSignal,
+        sender: Option<(i32, u32)>,
+        classified: Option<ClassifiedSignal>,
+        coalesce: bool,
+        dropped: &mut Vec<i32>,
+    ) -> bool {
+        match (self, raw.signal) {
+            (Sink::Std(_, Some(set)), Some(sig)) if !set.contains(&sig) => true,
+            (Sink::Std(s, _), Some(sig)) if coalesce => {
+                track(s.send_coalesced(sig), dropped, Signal::to_i32)
+            }
+            (Sink::Std(s, _), Some(sig)) => track(s.send(sig), dropped, Signal::to_i32),
+            (Sink::Raw(s), _) if coalesce => track(s.send_coalesced(raw), dropped, |r| r.number),
+            (Sink::Raw(s), _) => track(s.send(raw), dropped, |r| r.number),
+            (Sink::Info(s), Some(sig)) => {
+                let info = SignalInfo {
+                    signal: sig,
+                    sender_pid: sender.map(|(pid, _)| pid),
+                    sender_uid: sender.map(|(_, uid)| uid),
+                };
+                let sent = if coalesce {
+                    s.send_coalesced(info)
+                } else {
+                    s.send(info)
+                };
+                track(sent, dropped, |i| i.signal.to_i32())
+            }
+            (Sink::Classified(s), Some(_)) => match classified {
+                Some(c) if coalesce => track(s.send_coalesced(c), dropped, |c| c.signal.to_i32()),
+                Some(c) => track(s.send(c), dropped, |c| c.signal.to_i32()),
+                None => true,
+            },
+            #[cfg(feature = "crossbeam")]
+            (Sink::Crossbeam(s), Some(sig)) => s.send(sig).is_ok(),
+            #[cfg(feature = "tokio")]
+            (Sink::Tokio(s), Some(sig)) => s.send(sig).is_ok(),
+            #[cfg(feature = "async-std")]
+            (Sink::AsyncStd(s), Some(sig)) => s.try_send(sig).is_ok(),
+            _ => true,
+        }
+    }
+}
+
+// Turns what one of our channels said about a send into whether the
+// receiver is still there, noting the number of what it dropped, if
+// anything, in `dropped`.
+fn track<T>(sent: Result<Option<T>, T>, dropped: &mut Vec<i32>, number: fn(&T) -> i32) -> bool {
+    match sent {
+        Ok(Some(value)) => {
+            dropped.push(number(&value));
+            true
+        }
+        Ok(None) => true,
+        Err(_) => false,
+    }
+}
+
+// State shared between a `Signals` and the fan-out callback it registers
+// with the platform layer.
+struct Shared {
+    // Taken before `chaining` is changed, so the two stay in step.
+    handled: Mutex<Vec<i32>>,
+    // Set once the handler is installed, which it never is for
+    // `without_handlers`.
+    handler: OnceLock<platform::HandlerId>,
+    // Signals to stop handling once they've been received (see `oneshot`),
+    // and those that have been, whose stragglers are dropped.
+    oneshot: Mutex<Vec<i32>>,
+    released: Mutex<Vec<i32>>,
+    senders: Mutex<Vec<(usize, Sink)>>,
+    next_id: AtomicUsize,
+    ignored: Mutex<Vec<Signal>>,
+    observers: Mutex<Vec<Callback>>,
+    // Called with each signal a subscriber's channel drops; see `on_drop`.
+    drop_callbacks: Mutex<Vec<Callback>>,
+    // How many signals subscribers' channels have dropped, for
+    // `dropped_count`.
+    dropped: AtomicU64,
+    // The signals to coalesce; see `with_coalescing`.
+    coalescing: Mutex<Vec<Signal>>,
+    chaining: AtomicBool,
+    // Cleared as soon as the instance starts tearing down.
+    active: AtomicBool,
+    paused: AtomicBool,
+    // How often each signal number was received, for `counts`.
+    counts: Vec<AtomicU64>,
+    // The number of the latest signal received, for `last`; 0 before any.
+    last: AtomicI32,
+    buffer_while_paused: AtomicBool,
+    // What arrived during a pause, in order.
+    held: Mutex<Vec<platform::Delivery>>,
+    // The capacity of new subscriptions' channels; 0 means unbounded.
+    capacity: AtomicUsize,
+    overflow: Mutex<OverflowPolicy>,
+    // For `close` to stop the fan-out waiting on a full `Block` channel.
+    stoppers: Mutex<Vec<Weak<dyn channel::Stop>>>,
+    // Guarded by the `senders` lock, so that a subscriber either gets a
+    // terminating signal from the fan-out or from the replay, never both.
+    last_terminating: Mutex<Option<Signal>>,
+    #[cfg(feature = "log")]
+    log_level: Mutex<log::Level>,
+    #[cfg(feature = "tracing")]
+    tracing_level: Mutex<tracing::Level>,
+    terminating: Overrides,
+}
+
+// This instance's own say on which signals are terminating, for its
+// shutdown helpers; see `treat_as_terminating`.
+type Overrides = Arc<Mutex<Vec<(Signal, bool)>>>;
+
+fn is_terminating(overrides: &Overrides, sig: Signal) -> bool {
+    match overrides.lock().unwrap().iter().find(|(s, _)| *s == sig) {
+        Some((_, terminating)) => *terminating,
+        None => sig.is_terminating(),
+    }
+}
+
+impl Shared {
+    fn dispatch(&self, delivery: platform::Delivery) {
+        if self.release_oneshot(delivery.signal) {
+            return;
+        }
+        let raw = RawSignal::new(delivery.signal);
+        #[cfg(feature = "log")]
+        match raw.signal {
+            Some(sig) => log::log!(*self.log_level.lock().unwrap(), "received {}", sig),
+            None => log::log!(
+                *self.log_level.lock().unwrap(),
+                "received signal {}",
+                delivery.signal
+            ),
+        }
+        #[cfg(feature = "tracing")]
+        trace(*self.tracing_level.lock().unwrap(), raw);
+        if let Some(count) = usize::try_from(delivery.signal)
+            .ok()
+            .and_then(|n| self.counts.get(n))
+        {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+        self.last.store(delivery.signal, Ordering::Relaxed);
+        if let Some(sig) = raw.signal {
+            for f in self.observers.lock().unwrap().iter() {
+                f(sig);
+            }
+            if self.ignored.lock().unwrap().contains(&sig) {
+                return;
+            }
+        }
+        // Terminating signals get through a pause, so a shutdown request is
+        // never held back.
+        if self.paused.load(Ordering::SeqCst) && !raw.signal.is_some_and(|s| self.terminates(s)) {
+            if self.buffer_while_paused.load(Ordering::SeqCst) {
+                self.held.lock().unwrap().push(delivery);
+            }
+            return;
+        }
+        self.fan_out(delivery);
+    }
+
+    // Lets go of `sig` if it's a oneshot signal arriving for the first
+    // time. Returns true if it was let go of before, in which case this is a
+    // copy that was already on its way and is dropped.
+    fn release_oneshot(&self, sig: i32) -> bool {
+        let mut released = self.released.lock().unwrap();
+        if released.contains(&sig) {
+            return true;
+        }
+        let mut handled = self.handled.lock().unwrap();
+        let mut oneshot = self.oneshot.lock().unwrap();
+        if let Some(pos) = oneshot.iter().position(|n| *n == sig) {
+            oneshot.remove(pos);
+            released.push(sig);
+            handled.retain(|n| *n != sig);
+            if self.chaining.load(Ordering::SeqCst) {
+                platform::chain(&[sig], false);
+            }
+            if let Some(handler) = self.handler.get() {
+                platform::release_signal(*handler, sig);
+            }
+        }
+        false
+    }
+
+    // Pausing and resuming happen on the dispatch thread, in order with the
+    // signals around them, so that signals held back by a pause are
+    // delivered before any that arrive after it's lifted, and a quick
+    // pause/resume/pause can't end up unpaused.
+    fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        let held: Vec<platform::Delivery> = self.held.lock().unwrap().drain(..).collect();
+        for delivery in held {
+            self.fan_out(delivery);
+        }
+    }
+
+    fn fan_out(&self, delivery: platform::Delivery) {
+        let raw = RawSignal::new(delivery.signal);
+        // Terminating signals are never coalesced, so a shutdown request
+        // can't be mistaken for a duplicate and lost.
+        let coalesce = raw.signal.is_some_and(|sig| {
+            !self.terminates(sig) && self.coalescing.lock().unwrap().contains(&sig)
+        });
+        let mut senders = self.senders.lock().unwrap();
+        if let Some(sig) = raw.signal.filter(|sig| self.terminates(*sig)) {
+            *self.last_terminating.lock().unwrap() = Some(sig);
+        }
+        // Receivers normally unregister themselves when dropped, but this
+        // also catches any that were missed (e.g. tokio streams).
+        let classified = raw.signal.map(|signal| ClassifiedSignal {
+            signal,
+            action: signal.default_action(),
+            terminating: signal.is_terminating(),
+        });
+        let mut dropped = Vec::new();
+        senders.retain(|(_, s)| s.send(raw, delivery.sender, classified, coalesce, &mut dropped));
+        drop(senders);
+        if dropped.is_empty() {
+            return;
+        }
+        self.dropped
+            .fetch_add(dropped.len() as u64, Ordering::Relaxed);
+        let callbacks = self.drop_callbacks.lock().unwrap();
+        for sig in dropped
+            .into_iter()
+            .filter_map(|n| from_posix_signum(n).ok())
+        {
+            for f in callbacks.iter() {
+                f(sig);
+            }
+        }
+    }
+
+    fn remove(&self, id: usize) {
+        self.senders.lock().unwrap().retain(|(i, _)| *i != id);
+    }
+
+    // Registers `sink` and returns its id. Once the instance is closed, the
+    // sink is dropped straight away instead, so its receiver sees a closed
+    // channel rather than waiting forever.
+    fn add(&self, sink: Sink) -> usize {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut senders = self.senders.lock().unwrap();
+        if self.active.load(Ordering::SeqCst) {
+            senders.push((id, sink));
+        }
+        id
+    }
+
+    // Registers `sink`, unregistering it again when `receiver` is dropped.
+    fn attach<T>(this: &Arc<Shared>, sink: Sink, receiver: &mut Receiver<T>) {
+        let id = this.add(sink);
+        Shared::remove_on_drop(this, id, receiver);
+    }
+
+    fn remove_on_drop<T>(this: &Arc<Shared>, id: usize, receiver: &mut Receiver<T>) {
+        let shared = Arc::downgrade(this);
+        receiver.on_drop(Box::new(move || {
+            if let Some(shared) = shared.upgrade() {
+                shared.remove(id);
+            }
+        }));
+    }
+
+    // Whether this instance treats `sig` as terminating; see
+    // `treat_as_terminating`.
+    fn terminates(&self, sig: Signal) -> bool {
+        is_terminating(&self.terminating, sig)
+    }
+
+    // A channel for a new subscriber, bounded to the configured capacity.
+    // `signal` tells which signal a value is about, if any: it's never
+    // dropped if this instance treats that signal as terminating, as told
+    // at the time it's sent.
+    fn channel<T>(&self, signal: fn(&T) -> Option<Signal>) -> (channel::Sender<T>, Receiver<T>)
+    where
+        T: Send + 'static,
+    {
+        let overrides = Arc::clone(&self.terminating);
+        let protected =
+            move |value: &T| signal(value).is_some_and(|sig| is_terminating(&overrides, sig));
+        let policy = *self.overflow.lock().unwrap();
+        let (s, r) = match self.capacity.load(Ordering::SeqCst) {
+            0 => channel::channel(None, policy, protected),
+            n => channel::channel(Some(n), policy, protected),
+        };
+        if policy == OverflowPolicy::Block {
+            let mut stoppers = self.stoppers.lock().unwrap();
+            stoppers.retain(|stopper| stopper.strong_count() > 0);
+            stoppers.push(s.stopper());
+        }
+        (s, r)
+    }
+
+    // With `replay`, the latest terminating signal received so far, if
+    // any, is queued first; see `Signals::subscribe`.
+    fn subscribe(
+        this: &Arc<Shared>,
+        filter: Option<Vec<Signal>>,
+        replay: bool,
+    ) -> Receiver<Signal> {
+        let (s, mut r) = this.channel(|sig: &Signal| Some(*sig));
+        let filter_again = filter.clone();
+        let id = this.next_id.fetch_add(1, Ordering::SeqCst);
+        {
+            let mut senders = this.senders.lock().unwrap();
+            if let Some(sig) = (*this.last_terminating.lock().unwrap()).filter(|_| replay) {
+                if filter.as_ref().is_none_or(|set| set.contains(&sig)) {
+                    let _ = s.send(sig);
+                }
+            }
+            if this.active.load(Ordering::SeqCst) {
+                senders.push((id, Sink::Std(s, filter)));
+            }
+        }
+        Shared::remove_on_drop(this, id, &mut r);
+        Shared::resubscribe_with(this, &mut r, move |shared| {
+            Shared::subscribe(shared, filter_again.clone(), replay)
+        });
+        r
+    }
+
+    fn subscribe_raw(this: &Arc<Shared>) -> Receiver<RawSignal> {
+        let (s, mut r) = this.channel(|raw: &RawSignal| raw.signal);
+        Shared::attach(this, Sink::Raw(s), &mut r);
+        Shared::resubscribe_with(this, &mut r, Shared::subscribe_raw);
+        r
+    }
+
+    fn subscribe_info(this: &Arc<Shared>) -> Receiver<SignalInfo> {
+        let (s, mut r) = this.channel(|info: &SignalInfo| Some(info.signal));
+        Shared::attach(this, Sink::Info(s), &mut r);
+        Shared::resubscribe_with(this, &mut r, Shared::subscribe_info);
+        r
+    }
+
+    fn subscribe_classified(this: &Arc<Shared>) -> Receiver<ClassifiedSignal> {
+        let (s, mut r) = this.channel(|c: &ClassifiedSignal| Some(c.signal));
+        Shared::attach(this, Sink::Classified(s), &mut r);
+        Shared::resubscribe_with(this, &mut r, Shared::subscribe_classified);
+        r
+    }
+
+    // Lets `receiver` get a fresh subscription through `subscribe` for as
+    // long as this instance is around, and a closed one after that.
+    fn resubscribe_with<T, F>(this: &Arc<Shared>, receiver: &mut Receiver<T>, subscribe: F)
+    where
+        T: 'static,
+        F: Fn(&Arc<Shared>) -> Receiver<T> + Send + Sync + 'static,
+    {
+        let shared = Arc::downgrade(this);
+        receiver.on_resubscribe(Arc::new(move || match shared.upgrade() {
+            Some(shared) => subscribe(&shared),
+            None => channel::closed(),
+        }));
+    }
+}
+
+// Records `raw` as a tracing event. The level of an event has to be known
+// at compile time, hence one per level.
+#[cfg(feature = "tracing")]
+fn trace(level: tracing::Level, raw: RawSignal) {
+    use tracing::{event, Level};
+    let signal = match raw.signal {
+        Some(sig) => sig.to_string(),
+        None => raw.number.to_string(),
+    };
+    let number = raw.number;
+    if level == Level::ERROR {
+        event!(Level::ERROR, %signal, number, "received signal");
+    } else if level == Level::WARN {
+        event!(Level::WARN, %signal, number, "received signal");
+    } else if level == Level::INFO {
+        event!(Level::INFO, %signal, number, "received signal");
+    } else if level == Level::DEBUG {
+        event!(Level::DEBUG, %signal, number, "received signal");
+    } else {
+        event!(Level::TRACE, %signal, number, "received signal");
+    }
+}
+
+type Callback = Box<dyn Fn(Signal) + Send>;
+
+// Fails for signal numbers that can't be handled at all.
+fn check(signals: &[i32]) -> Result<(), SignalError> {
+    if let Some(sig_num) = signals.iter().find(|n| !platform::is_valid(**n)) {
+        return Err(SignalError::UnsupportedSignal(*sig_num));
+    }
+    for sig_num in signals {
+        match *sig_num {
+            SIGKILL => return Err(SignalError::Uncatchable(Signal::Kill)),
+            SIGSTOP => return Err(SignalError::Uncatchable(Signal::Stop)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// How an instance's dispatch thread is set up; see `SignalsBuilder`.
+struct DispatchThread {
+    name: String,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    nice: Option<i32>,
+}
+
+impl Default for DispatchThread {
+    fn default() -> DispatchThread {
+        DispatchThread {
+            name: String::from("signal-dispatch"),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            nice: None,
+        }
+    }
+}
+
+// What the platform layer's callback hands to an instance's dispatch thread.
+enum Event {
+    Signal(platform::Delivery),
+    Pause,
+    Resume,
+    Shutdown,
+}
+
+/// Handles a set of signals and fans each one out to any number of
+/// subscribers.
+///
+/// Any number of instances can exist at once, in the same library or in
+/// independent ones, and they don't fight over signals: the OS-level handler
+/// is installed once per signal and shared, and a signal handled by several
+/// instances is delivered to every one of them. It's only put back to its
+/// previous disposition once the last instance handling it is gone. The
+/// legacy `SignalSender` API takes part in the same sharing. Code outside
+/// this crate that installs its own handler afterwards still replaces ours,
+/// as the OS only keeps one per signal; see `with_chaining` for going the
+/// other way, cooperating with a handler that was there first.
+pub struct Signals {
+    shared: Arc<Shared>,
+    events: mpsc::Sender<Event>,
+    dispatcher: Option<JoinHandle<()>>,
+    callbacks: Arc<Mutex<Vec<(Signal, Callback)>>>,
+    callback_thread: Mutex<Option<JoinHandle<()>>>,
+    // The subscriber behind `recv`, created the first time it's called.
+    default: Mutex<Option<Receiver<Signal>>>,
+    // The subscriber behind `as_raw_fd` and `drain_ready`, created the
+    // first time either is called, and the pipe that tells when it has
+    // something. The pipe is created up front, so that running out of file
+    // descriptors is an error from the constructor rather than a panic in
+    // `as_raw_fd`.
+    #[cfg(unix)]
+    ready: Mutex<Option<Receiver<Signal>>>,
+    #[cfg(unix)]
+    ready_read: OwnedFd,
+    #[cfg(unix)]
+    ready_write: Arc<OwnedFd>,
+}
+
+impl Signals {
+    /// Handles every signal in `all()`. That leaves out SIGPIPE, SIGTSTP and
+    /// SIGCHLD, whose default behaviour most programs rely on; use
+    /// `with_signals` to handle them too.
+    pub fn new() -> Result<Signals, SignalError> {
+        Signals::with_signals(&all())
+    }
+
+    /// Handles just `signals`. Passing `Kill` or `Stop` fails with
+    /// `Uncatchable`, as the OS won't let anyone handle them. On UNIX, this
+    /// also fails with `NotifierFailed` if the pipe behind `as_raw_fd`, or
+    /// the one the crate passes signals on through, can't be created. With
+    /// the `signalfd` feature, this has to be called before the program
+    /// starts any threads; see the README.
+    pub fn with_signals(signals: &[Signal]) -> Result<Signals, SignalError> {
+        let numbers: Vec<i32> = signals.iter().map(Signal::to_i32).collect();
+        Signals::with_raw_signals(&numbers)
+    }
+
+    /// Handles `signals` only while `f` runs: the handlers are installed,
+    /// `f` gets the instance, and once it returns (or panics) the instance
+    /// is dropped, which puts back the dispositions the signals had before.
+    pub fn scope<R, F>(signals: &[Signal], f: F) -> Result<R, SignalError>
+    where
+        F: FnOnce(&Signals) -> R,
+    {
+        let signals = Signals::with_signals(signals)?;
+        Ok(f(&signals))
+    }
+
+    /// Like `with_signals`, but takes signal numbers, which may include
+    /// signals that `Signal` doesn't model. Those are only delivered to
+    /// `subscribe_raw` receivers.
+    pub fn with_raw_signals(signals: &[i32]) -> Result<Signals, SignalError> {
+        Signals::start(signals, DispatchThread::default(), true)
+    }
+
+    /// Makes an instance that handles `signals` as far as it's concerned,
+    /// but without installing anything with the OS, for testing subscriber
+    /// logic. Nothing the OS delivers reaches it; signals only arrive
+    /// through `test_inject` (or `prime`), so tests using it are
+    /// deterministic and can run in parallel, unaffected by the
+    /// process-wide dispositions other tests set up. It fails like
+    /// `with_signals` for `Kill` and `Stop`.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn without_handlers(signals: &[Signal]) -> Result<Signals, SignalError> {
+        let numbers: Vec<i32> = signals.iter().map(Signal::to_i32).collect();
+        Signals::start(&numbers, DispatchThread::default(), false)
+    }
+
+    // Sets up an instance for `signals`, installing the OS handler for them
+    // if `install` is set.
+    fn start(
+        signals: &[i32],
+        config: DispatchThread,
+        install: bool,
+    ) -> Result<Signals, SignalError> {
+        check(signals)?;
+        let shared = Arc::new(Shared {
+            handled: Mutex::new(signals.to_vec()),
+            handler: OnceLock::new(),
+            oneshot: Mutex::new(Vec::new()),
+            released: Mutex::new(Vec::new()),
+            senders: Mutex::new(Vec::new()),
+            next_id: AtomicUsize::new(0),
+            ignored: Mutex::new(Vec::new()),
+            observers: Mutex::new(Vec::new()),
+            drop_callbacks: Mutex::new(Vec::new()),
+            dropped: AtomicU64::new(0),
+            coalescing: Mutex::new(Vec::new()),
+            chaining: AtomicBool::new(false),
+            active: AtomicBool::new(true),
+            paused: AtomicBool::new(false),
+            counts: (0..platform::MAX_SIGNAL)
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+            last: AtomicI32::new(0),
+            buffer_while_paused: AtomicBool::new(true),
+            held: Mutex::new(Vec::new()),
+            capacity: AtomicUsize::new(0),
+            overflow: Mutex::new(OverflowPolicy::default()),
+            stoppers: Mutex::new(Vec::new()),
+            last_terminating: Mutex::new(None),
+            #[cfg(feature = "log")]
+            log_level: Mutex::new(log::Level::Info),
+            #[cfg(feature = "tracing")]
+            tracing_level: Mutex::new(tracing::Level::INFO),
+            terminating: Arc::new(Mutex::new(Vec::new())),
+        });
+        #[cfg(unix)]
+        let (ready_read, ready_write) =
+            platform::notifier().map_err(|err| SignalError::NotifierFailed {
+                errno: err.raw_os_error().unwrap_or(0),
+            })?;
+        // The platform layer's worker thread is shared by every instance,
+        // so all it does is pass signals on; the fan-out to subscribers
+        // happens on a thread of our own, which `Drop` stops again.
+        let (events, incoming) = mpsc::channel();
+        let fan_out = Arc::clone(&shared);
+        // Created here rather than on the thread, so that it's a child of
+        // whatever span the instance was created in.
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("signal-handler");
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let nice = config.nice;
+        let dispatcher = thread::Builder::new()
+            .name(config.name)
+            .spawn(move || {
+                platform::own_thread();
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                if let Some(level) = nice {
+                    platform::set_nice(level);
+                }
+                #[cfg(feature = "tracing")]
+                let _entered = span.entered();
+                for event in incoming {
+                    match event {
+                        Event::Signal(delivery) => fan_out.dispatch(delivery),
+                        Event::Pause => fan_out.pause(),
+                        Event::Resume => fan_out.resume(),
+                        Event::Shutdown => break,
+                    }
+                }
+            })
+            .expect("failed to spawn thread");
+        if install {
+            let forward = events.clone();
+            let installed = platform::set_handler(signals, move |signals| {
+                for delivery in signals {
+                    let _ = forward.send(Event::Signal(*delivery));
+                }
+            });
+            match installed {
+                Ok(handler) => {
+                    let _ = shared.handler.set(handler);
+                }
+                Err(err) => {
+                    let _ = events.send(Event::Shutdown);
+                    let _ = dispatcher.join();
+                    return Err(err);
+                }
+            }
+        }
+        Ok(Signals {
+            shared,
+            events,
+            dispatcher: Some(dispatcher),
+            callbacks: Arc::new(Mutex::new(Vec::new())),
+            callback_thread: Mutex::new(None),
+            default: Mutex::new(None),
+            #[cfg(unix)]
+            ready: Mutex::new(None),
+            #[cfg(unix)]
+            ready_read,
+            #[cfg(unix)]
+            ready_write: Arc::new(ready_write),
+        })
+    }
+
+    /// Coalesces the signals in `set`, replacing whatever set was given
+    /// before; an empty one turns coalescing off, which is how instances
+    /// start. A signal in the set is not queued for a subscriber that still
+    /// has the same signal unread, so a slow consumer sees it at most once
+    /// per drain of its receiver, however often it was raised in the
+    /// meantime; e.g. a burst of SIGWINCH or SIGHUP only needs handling
+    /// once. Terminating signals are always queued, even if they're in
+    /// `set`. Pass `&all()` to coalesce everything else. The crossbeam,
+    /// tokio and async-std channels aren't coalesced.
+    pub fn with_coalescing(self, set: &[Signal]) -> Signals {
+        *self.shared.coalescing.lock().unwrap() = set.to_vec();
+        self
+    }
+
+    /// Turns chaining on or off. While it's on, every handled signal is also
+    /// passed on to the handler that was installed for it before this crate
+    /// took over (e.g. by a runtime or another library), so they keep
+    /// working. That handler is called from within the signal handler, just
+    /// like it would have been without this crate, right after the signal
+    /// has been queued for this instance, so subscribers usually see it a
+    /// moment later. Signals whose previous disposition was the default
+    /// action or "ignore" aren't passed on: there is no handler to call.
+    ///
+    /// Chaining is per signal for the whole process, so it's on for a
+    /// signal as long as any instance handling it asked for it. On Windows
+    /// this does nothing, as other console handlers are always chained, and
+    /// neither does it with the `signalfd` feature, as signals read from
+    /// the signalfd never run a handler at all.
+    pub fn with_chaining(self, chain: bool) -> Signals {
+        {
+            let handled = self.shared.handled.lock().unwrap();
+            if self.shared.chaining.swap(chain, Ordering::SeqCst) != chain {
+                platform::chain(&handled, chain);
+            }
+        }
+        self
+    }
+
+    /// Chooses what happens to signals that arrive while fan-out is paused
+    /// (see `pause`): with `true`, the default, they're held back and
+    /// delivered on `resume`; with `false` they're dropped.
+    pub fn with_pause_buffering(self, buffer: bool) -> Signals {
+        self.shared
+            .buffer_while_paused
+            .store(buffer, Ordering::SeqCst);
+        self
+    }
+
+    /// Stops handing signals to subscribers until `resume` is called,
+    /// without tearing anything down, e.g. around an operation that would
+    /// cause a storm of SIGCHLDs. What arrives in the meantime is held back
+    /// or dropped, as chosen with `with_pause_buffering`; either way the
+    /// observers still see it as it arrives. Terminating signals are never
+    /// paused: they're delivered right away, ahead of anything held back.
+    pub fn pause(&self) {
+        let _ = self.events.send(Event::Pause);
+    }
+
+    /// Lifts a `pause`, first delivering whatever was held back during it,
+    /// in the order it arrived.
+    pub fn resume(&self) {
+        let _ = self.events.send(Event::Resume);
+    }
+
+    /// Returns a receiver for every signal this instance handles. It's safe
+    /// to call from any thread at any time, including while signals are
+    /// arriving. The receiver gets every signal dispatched after `subscribe`
+    /// returns; earlier ones aren't replayed, with one exception: if a
+    /// terminating signal has already been received, the most recent one is
+    /// queued first so late subscribers still learn that the process is
+    /// shutting down.
+    pub fn subscribe(&self) -> Receiver<Signal> {
+        Shared::subscribe(&self.shared, None, true)
+    }
+
+    /// Like `subscribe`, but also returns a handle that can close the
+    /// receiver from another thread, to stop a thread blocked on it without
+    /// waiting for a signal.
+    pub fn subscribe_cancellable(&self) -> (Receiver<Signal>, CancelHandle) {
+        let receiver = self.subscribe();
+        let handle = receiver.cancel_handle();
+        (receiver, handle)
+    }
+
+    /// Like `subscribe`, but only signals in `set` are forwarded to the
+    /// returned receiver; others are skipped by the fan-out rather than woken
+    /// up for. The replayed terminating signal is subject to the filter too.
+    pub fn subscribe_filtered(&self, set: &[Signal]) -> Receiver<Signal> {
+        Shared::subscribe(&self.shared, Some(set.to_vec()), true)
+    }
+
+    /// Subscribes to signals along with the number the OS delivered, which
+    /// is the only way to see signals that `Signal` has no variant for.
+    pub fn subscribe_raw(&self) -> Receiver<RawSignal> {
+        Shared::subscribe_raw(&self.shared)
+    }
+
+    /// Like `subscribe`, but each signal comes with the pid and uid of the
+    /// process that sent it, where the OS provides them, so daemons can tell
+    /// who is asking them to act.
+    pub fn subscribe_info(&self) -> Receiver<SignalInfo> {
+        Shared::subscribe_info(&self.shared)
+    }
+
+    /// Changes the signals this instance handles to `set`, e.g. after a
+    /// configuration reload: handlers are installed for signals that are
+    /// new to it, while signals no longer in `set` get back the disposition
+    /// they had before (unless another instance still handles them). It
+    /// fails like `with_signals`, or if a new handler can't be installed,
+    /// in which case nothing changes. Pending `oneshot`s for removed
+    /// signals are forgotten.
+    ///
+    /// Signals that were already received when this is called are still
+    /// delivered, even if they're being removed; only later ones are
+    /// affected. Subscribers keep their subscriptions, filters included.
+    ///
+    /// With the `signalfd` feature, removed signals are only unblocked in
+    /// the calling thread; see the README.
+    pub fn reconfigure(&self, set: &[Signal]) -> Result<(), SignalError> {
+        let mut numbers: Vec<i32> = Vec::new();
+        for sig_num in set.iter().map(Signal::to_i32) {
+            if !numbers.contains(&sig_num) {
+                numbers.push(sig_num);
+            }
+        }
+        check(&numbers)?;
+        let mut released = self.shared.released.lock().unwrap();
+        let mut handled = self.shared.handled.lock().unwrap();
+        let mut oneshot = self.shared.oneshot.lock().unwrap();
+        if let Some(handler) = self.shared.handler.get() {
+            platform::set_signals(*handler, &numbers)
+                .map_err(|(signal, errno)| SignalError::HandlerInstallFailed { signal, errno })?;
+        }
+        if self.shared.chaining.load(Ordering::SeqCst) {
+            let added: Vec<i32> = numbers
+                .iter()
+                .filter(|n| !handled.contains(n))
+                .copied()
+                .collect();
+            let removed: Vec<i32> = handled
+                .iter()
+                .filter(|n| !numbers.contains(n))
+                .copied()
+                .collect();
+            platform::chain(&added, true);
+            platform::chain(&removed, false);
+        }
+        released.retain(|n| !numbers.contains(n));
+        oneshot.retain(|n| numbers.contains(n));
+        *handled = numbers;
+        Ok(())
+    }
+
+    /// Like `subscribe`, but each signal comes classified (see
+    /// `ClassifiedSignal`), worked out once by the fan-out for every such
+    /// subscriber rather than by each of them.
+    pub fn subscribe_classified(&self) -> Receiver<ClassifiedSignal> {
+        Shared::subscribe_classified(&self.shared)
+    }
+
+    /// Stops `sig` from reaching any subscriber, without restoring its
+    /// default behavior: the signal is still caught, just dropped.
+    pub fn ignore(&self, sig: Signal) {
+        let mut ignored = self.shared.ignored.lock().unwrap();
+        if !ignored.contains(&sig) {
+            ignored.push(sig);
+        }
+    }
+
+    /// Undoes `ignore`, so `sig` is delivered to subscribers again.
+    pub fn unignore(&self, sig: Signal) {
+        self.shared.ignored.lock().unwrap().retain(|s| *s != sig);
+    }
+
+    /// Handles `sig` just once more: the next time it arrives, it's
+    /// delivered as usual, to every subscriber that wants it (each gets that
+    /// one delivery), and then this instance stops handling it for good, as
+    /// if it had never been among its signals. The disposition `sig` had
+    /// before this instance took it over, normally the default action, is
+    /// put back then, unless another instance still handles it. Copies
+    /// raised before that happened but not yet delivered are dropped too.
+    /// This does nothing for a signal this instance doesn't handle.
+    ///
+    /// With the `signalfd` feature, the signal stays blocked in the thread
+    /// that created this instance and its children, so the default action
+    /// doesn't come back for them (see the README).
+    pub fn oneshot(&self, sig: Signal) {
+        let sig_num = sig.to_i32();
+        let handled = self.shared.handled.lock().unwrap();
+        let mut oneshot = self.shared.oneshot.lock().unwrap();
+        if handled.contains(&sig_num) && !oneshot.contains(&sig_num) {
+            oneshot.push(sig_num);
+        }
+    }
+
+    /// Calls `f` every time `sig` arrives; several closures can be registered
+    /// for the same signal and all of them run. The closures never run in
+    /// the signal handler itself, but one after the other on a thread that
+    /// is started by the first call to `on` and stops when this `Signals` is
+    /// dropped. They must not call `on` themselves.
+    pub fn on<F>(&self, sig: Signal, f: F)
+    where
+        F: Fn(Signal) + Send + 'static,
+    {
+        self.callbacks.lock().unwrap().push((sig, Box::new(f)));
+        let mut callback_thread = self.callback_thread.lock().unwrap();
+        if callback_thread.is_none() {
+            let receiver = self.subscribe();
+            let callbacks = Arc::clone(&self.callbacks);
+            *callback_thread = Some(thread::spawn(move || {
+                platform::own_thread();
+                for sig in receiver {
+                    for (s, f) in callbacks.lock().unwrap().iter() {
+                        if *s == sig {
+                            f(sig);
+                        }
+                    }
+                }
+            }));
+        }
+    }
+
+    /// Passes every signal in `set` that this instance receives from now on
+    /// to `child`, as supervisors do, from a thread of its own that runs
+    /// until this instance is closed or the child exits. Signals received
+    /// before the call aren't passed on, not even the terminating one
+    /// `subscribe` replays, which would kill a freshly respawned child.
+    /// `Kill` and `Stop` in `set` are accepted but never forwarded, as they
+    /// can't be received in the first place.
+    ///
+    /// Before each signal, the child is checked for having exited, without
+    /// reaping it, so that once it's gone nothing is sent to whatever
+    /// process reuses its pid. That leaves a small window: if the child is
+    /// waited for by another thread right between the check and the signal,
+    /// and its pid is reused at once, the signal still goes to the new
+    /// process.
+    #[cfg(unix)]
+    pub fn forward_to(&self, child: &std::process::Child, set: &[Signal]) {
+        let pid = child.id() as i32;
+        let receiver = Shared::subscribe(&self.shared, Some(set.to_vec()), false);
+        thread::spawn(move || {
+            platform::own_thread();
+            for sig in receiver {
+                if !platform::send_to_child(pid, sig.to_i32()) {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Blocks delivery of the signals in `set` to the calling thread until
+    /// the returned guard is dropped, which puts the previous mask back. A
+    /// signal raised in the meantime stays pending and is delivered once
+    /// it's unblocked, only once however many times it was raised (POSIX
+    /// doesn't queue standard signals).
+    ///
+    /// Signal masks are per thread: this keeps signals from interrupting the
+    /// calling thread (e.g. ones it `raise`s itself), but a signal sent to
+    /// the whole process can still be delivered to any other thread that
+    /// doesn't block it. The guard can't be sent to another thread for the
+    /// same reason.
+    #[cfg(unix)]
+    pub fn mask(&self, set: &[Signal]) -> MaskGuard {
+        let numbers: Vec<i32> = set.iter().map(Signal::to_i32).collect();
+        MaskGuard {
+            previous: platform::block(&numbers),
+            _not_send: PhantomData,
+        }
+    }
+
+    /// The signals the calling thread currently blocks, for diagnosing
+    /// signals that never seem to arrive. This only reads the mask, it
+    /// doesn't change it. Like `mask`, it's about the calling thread only.
+    /// Blocked signals `Signal` doesn't model are left out.
+    #[cfg(unix)]
+    pub fn current_mask() -> Vec<Signal> {
+        platform::blocked()
+            .into_iter()
+            .filter_map(|n| from_posix_signum(n).ok())
+            .collect()
+    }
+
+    /// The signals raised but not delivered yet because they're blocked,
+    /// pending either for the calling thread or for the whole process, to
+    /// see why a `mask`ed signal hasn't shown up. This only reads the set.
+    /// Pending signals `Signal` doesn't model are left out.
+    #[cfg(unix)]
+    pub fn pending_signals() -> Vec<Signal> {
+        platform::pending()
+            .into_iter()
+            .filter_map(|n| from_posix_signum(n).ok())
+            .collect()
+    }
+
+    /// Feeds `sig` to this instance as if it had just been received, without
+    /// raising anything: the observers, `ignore`, filters and coalescing all
+    /// treat it like the real thing, and current subscribers receive it in
+    /// order with real signals. Useful for running the "signal received"
+    /// path once at startup, e.g. treating launch like a SIGHUP reload. Like
+    /// the OS, this delivers nothing for a signal this instance doesn't
+    /// handle.
+    pub fn prime(&self, sig: Signal) {
+        if !self.shared.handled.lock().unwrap().contains(&sig.to_i32()) {
+            return;
+        }
+        let _ = self.events.send(Event::Signal(platform::Delivery {
+            signal: sig.to_i32(),
+            sender: None,
+        }));
+    }
+
+    /// Feeds `sig` to this instance as if the OS had delivered it, for
+    /// tests: it's `prime` by another name, to make tests read as what they
+    /// are. This bypasses real delivery entirely, so it says nothing about
+    /// whether the handler would have caught the signal; pair it with
+    /// `without_handlers` to keep the OS out of a test altogether.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn test_inject(&self, sig: Signal) {
+        self.prime(sig);
+    }
+
+    /// Whether this instance still has its handlers installed and its
+    /// dispatch thread running, i.e. it hasn't been `close`d.
+    pub fn is_active(&self) -> bool {
+        self.shared.active.load(Ordering::SeqCst)
+            && self.dispatcher.as_ref().is_some_and(|d| !d.is_finished())
+    }
+
+    /// Calls `f` with every signal this instance receives, before it's
+    /// handed to subscribers and regardless of `ignore` or subscription
+    /// filters, without consuming it. This is meant for logging and metrics.
+    /// `f` runs on this instance's dispatch thread, never in the signal
+    /// handler, so it should be quick: subscribers wait for it. It must not
+    /// call `with_observer` itself.
+    pub fn with_observer<F>(&self, f: F)
+    where
+        F: Fn(Signal) + Send + 'static,
+    {
+        self.shared.observers.lock().unwrap().push(Box::new(f));
+    }
+
+    /// Calls `f` with every signal a subscriber's channel drops: one that a
+    /// full `buffer_capacity` queue had no room for (which one, and whether
+    /// any is dropped at all, is up to the `overflow_policy`), or a
+    /// duplicate that `with_coalescing` throws away. A signal dropped by several
+    /// subscribers is reported once for each. Like an observer, `f` runs on
+    /// this instance's dispatch thread, never in the signal handler, so it
+    /// should be quick, and it must not call `on_drop` itself. Signals
+    /// `Signal` doesn't model are counted by `dropped_count` but not
+    /// reported here.
+    pub fn on_drop<F>(&self, f: F)
+    where
+        F: Fn(Signal) + Send + 'static,
+    {
+        self.shared.drop_callbacks.lock().unwrap().push(Box::new(f));
+    }
+
+    /// How many signals subscribers' channels have dropped since this
+    /// instance was created, counted the same way `on_drop` reports them.
+    /// Signals that were ignored, filtered out or discarded during a pause
+    /// without buffering weren't dropped under pressure and don't count.
+    pub fn dropped_count(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// How many times each signal has been received since this instance was
+    /// created (or since `reset_counts`). Every received signal counts, even
+    /// ones that were ignored, filtered out by every subscriber or held back
+    /// by a pause, as do `prime`d ones. Signals never received are left out.
+    pub fn counts(&self) -> HashMap<Signal, u64> {
+        self.shared
+            .counts
+            .iter()
+            .enumerate()
+            .filter_map(|(n, count)| {
+                let sig = from_posix_signum(n as i32).ok()?;
+                match count.load(Ordering::Relaxed) {
+                    0 => None,
+                    count => Some((sig, count)),
+                }
+            })
+            .collect()
+    }
+
+    /// The signal this instance received most recently, if any, e.g. for a
+    /// status display. Every signal that `counts` counts updates it,
+    /// ignored and primed ones included; one that `Signal` doesn't model
+    /// makes this `None`. Only the latest is kept, and reading it takes
+    /// nothing away from subscribers.
+    pub fn last(&self) -> Option<Signal> {
+        from_posix_signum(self.shared.last.load(Ordering::Relaxed)).ok()
+    }
+
+    /// Sets every count reported by `counts` back to zero.
+    pub fn reset_counts(&self) {
+        for count in &self.shared.counts {
+            count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// The signals this instance installed handlers for and currently
+    /// delivers, i.e. leaving out any that were `ignore`d.
+    pub fn handled(&self) -> Vec<Signal> {
+        let ignored = self.shared.ignored.lock().unwrap();
+        self.shared
+            .handled
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|n| from_posix_signum(*n).ok())
+            .filter(|sig| !ignored.contains(sig))
+            .collect()
+    }
+
+    /// Blocks until the next signal arrives, for programs that don't need a
+    /// subscriber of their own. It reads from an internal subscriber created
+    /// by the first call, so signals that arrived before then are missed
+    /// (apart from the terminating signal `subscribe` replays); after that,
+    /// nothing is lost between calls.
+    pub fn recv(&self) -> Result<Signal, SignalError> {
+        let mut default = self.default.lock().unwrap();
+        default.get_or_insert_with(|| self.subscribe()).recv()
+    }
+
+    /// Returns every signal received since the last call, without blocking,
+    /// once `as_raw_fd` has said there are some. Like `recv`, this reads
+    /// from an internal subscriber, created by the first call to either
+    /// method, so signals that arrived before then are missed.
+    #[cfg(unix)]
+    pub fn drain_ready(&self) -> Vec<Signal> {
+        let mut ready = self.ready.lock().unwrap();
+        let receiver = ready.get_or_insert_with(|| self.ready_subscriber());
+        // The pipe is emptied first, so a signal that arrives while the
+        // queue is being drained makes it readable again.
+        platform::clear(&self.ready_read);
+        receiver.drain_available()
+    }
+
+    #[cfg(unix)]
+    fn ready_subscriber(&self) -> Receiver<Signal> {
+        let write = Arc::clone(&self.ready_write);
+        let receiver = self.subscribe();
+        receiver.on_send(Box::new(move || platform::notify(&write)));
+        receiver
+    }
+
+    /// Calls `handler` with every signal from a new subscription, until it
+    /// returns `ControlFlow::Break` or this instance is closed, for the
+    /// usual loop over `subscribe()` with a match inside. Only signals that
+    /// arrive after the call are handled: unlike `subscribe`, this doesn't
+    /// start with a terminating signal received earlier.
+    ///
+    /// ```no_run
+    /// use signal_msg::{Signal, Signals};
+    /// use std::ops::ControlFlow;
+    ///
+    /// let signals = Signals::new().expect("couldn't install signal handlers");
+    /// signals.run(|sig| match sig {
+    ///     Signal::Hup => {
+    ///         println!("reloading");
+    ///         ControlFlow::Continue(())
+    ///     }
+    ///     sig if sig.is_terminating() => ControlFlow::Break(()),
+    ///     _ => ControlFlow::Continue(()),
+    /// });
+    /// ```
+    pub fn run<F>(&self, mut handler: F)
+    where
+        F: FnMut(Signal) -> ControlFlow<()>,
+    {
+        for sig in Shared::subscribe(&self.shared, None, false) {
+            if handler(sig).is_break() {
+                break;
+            }
+        }
+    }
+
+    /// Makes this instance treat `sig` as terminating or not, whatever
+    /// `Signal::is_terminating` says, e.g. to have SIGHUP mean "shut down"
+    /// rather than "reload" in some deployment. That's what its shutdown
+    /// helpers (`wait_for_shutdown`, `shutdown_guard` and
+    /// `shutdown_signal`) go by, and it comes with the guarantees that
+    /// terminating signals get: `sig` is then never dropped by a full
+    /// subscription, coalesced or held back by a pause, and it's what late
+    /// subscribers get replayed. A signal marked as not terminating loses
+    /// them instead. `Signal::is_terminating` itself keeps its answer, as
+    /// does `ClassifiedSignal::terminating`.
+    pub fn treat_as_terminating(&self, sig: Signal, yes: bool) {
+        let mut overrides = self.shared.terminating.lock().unwrap();
+        overrides.retain(|(s, _)| *s != sig);
+        overrides.push((sig, yes));
+    }
+
+    /// Blocks until a terminating signal (see `Signal::is_terminating` and
+    /// `treat_as_terminating`) arrives and returns it; any other signals are
+    /// consumed and dropped.
+    pub fn wait_for_shutdown(&self) -> Result<Signal, SignalError> {
+        let receiver = self.subscribe();
+        loop {
+            let sig = receiver.recv()?;
+            if is_terminating(&self.shared.terminating, sig) {
+                return Ok(sig);
+            }
+        }
+    }
+
+    /// Blocks until one of the signals in `set` arrives and returns it;
+    /// anything else received in the meantime is dropped. Only signals that
+    /// arrive after the call count: unlike `subscribe`, this doesn't replay
+    /// a terminating signal received earlier.
+    pub fn wait_for(&self, set: &[Signal]) -> Result<Signal, SignalError> {
+        let receiver = Shared::subscribe(&self.shared, None, false);
+        loop {
+            let sig = receiver.recv()?;
+            if set.contains(&sig) {
+                return Ok(sig);
+            }
+        }
+    }
+
+    /// Like `wait_for`, but gives up after `dur`, returning `Ok(None)`. The
+    /// deadline is for the whole call: signals outside `set` never reach
+    /// the subscription it waits on, so they can't restart the clock. Like
+    /// `wait_for`, it only sees signals that arrive after the call.
+    pub fn wait_for_timeout(
+        &self,
+        set: &[Signal],
+        dur: Duration,
+    ) -> Result<Option<Signal>, SignalError> {
+        Shared::subscribe(&self.shared, Some(set.to_vec()), false).recv_timeout(dur)
+    }
+
+    /// Blocks until `sig` has arrived `n` times since the call, dropping
+    /// anything else received in the meantime. A terminating `sig` that
+    /// arrived before the call doesn't count, unlike the one `subscribe`
+    /// replays. If `sig` is coalesced (see `with_coalescing`), repeats of
+    /// `sig` that arrive faster than they're counted collapse into one, so
+    /// it can take more than `n` raises to get here. Fails with
+    /// `ChannelClosed` if the instance is closed first.
+    pub fn wait_count(&self, sig: Signal, n: usize) -> Result<(), SignalError> {
+        let receiver = Shared::subscribe(&self.shared, Some(vec![sig]), false);
+        for _ in 0..n {
+            receiver.recv()?;
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over shutdown requests that implements the usual
+    /// "press Ctrl-C again to force quit" behavior; see `ShutdownGuard`.
+    pub fn shutdown_guard(&self, grace: Duration) -> ShutdownGuard {
+        ShutdownGuard {
+            receiver: self.subscribe(),
+            terminating: Arc::clone(&self.shared.terminating),
+            grace,
+            first: None,
+        }
+    }
+
+    /// Subscribes through a crossbeam channel, so signals can be one branch
+    /// of a `crossbeam_channel::select!` alongside the program's other
+    /// channels:
+    ///
+    /// ```no_run
+    /// # let signals = signal_msg::Signals::new().unwrap();
+    /// # let (_work_sender, work) = crossbeam_channel::unbounded::<String>();
+    /// let signal_receiver = signals.subscribe_crossbeam();
+    /// loop {
+    ///     crossbeam_channel::select! {
+    ///         recv(work) -> job => println!("working on {:?}", job),
+    ///         recv(signal_receiver) -> sig => {
+    ///             println!("got {:?}, stopping", sig);
+    ///             break;
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "crossbeam")]
+    pub fn subscribe_crossbeam(&self) -> crossbeam_channel::Receiver<Signal> {
+        let (s, r) = crossbeam_channel::unbounded();
+        self.shared.add(Sink::Crossbeam(s));
+        r
+    }
+
+    /// Returns an async stream of every signal, for use with tokio. See
+    /// `async_std_stream` for async-std; a program normally enables just the
+    /// feature matching its runtime, though both can be on at once.
+    ///
+    /// The stream is fed from an unbounded channel, so polling it is
+    /// cancellation-safe: dropping a pending `next()` future (e.g. in a
+    /// losing `select!` branch) never loses a signal, which stays queued for
+    /// the next poll.
+    #[cfg(feature = "tokio")]
+    pub fn stream(&self) -> impl tokio_stream::Stream<Item = Signal> {
+        let (s, r) = tokio::sync::mpsc::unbounded_channel();
+        self.shared.add(Sink::Tokio(s));
+        tokio_stream::wrappers::UnboundedReceiverStream::new(r)
+    }
+
+    /// Returns a future that resolves to the first terminating signal (see
+    /// `treat_as_terminating`), the async counterpart of
+    /// `wait_for_shutdown`. Each call makes an independent future with a
+    /// subscription of its own, so there can be any number of them, each
+    /// resolving on its own; if a terminating signal was already received,
+    /// a new one resolves straight away. If this instance is closed first,
+    /// it resolves to `ChannelClosed` instead, so a `select!` branch waiting
+    /// on it doesn't hang forever.
+    ///
+    /// ```no_run
+    /// use signal_msg::Signals;
+    ///
+    /// # async fn work() {}
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let signals = Signals::new().expect("couldn't install signal handlers");
+    ///     tokio::select! {
+    ///         _ = work() => println!("work done"),
+    ///         sig = signals.shutdown_signal() => match sig {
+    ///             Ok(sig) => println!("got {}, stopping", sig),
+    ///             Err(err) => println!("stopped listening for signals: {}", err),
+    ///         },
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn shutdown_signal(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Signal, SignalError>> {
+        let (s, mut r) = tokio::sync::mpsc::unbounded_channel();
+        let replay = s.clone();
+        self.shared.add(Sink::Tokio(s));
+        // Checked only once subscribed, so a signal can't slip in between;
+        // at worst it's queued twice, and only the first one counts.
+        if let Some(sig) = *self.shared.last_terminating.lock().unwrap() {
+            let _ = replay.send(sig);
+        }
+        let terminating = Arc::clone(&self.shared.terminating);
+        async move {
+            while let Some(sig) = r.recv().await {
+                if is_terminating(&terminating, sig) {
+                    return Ok(sig);
+                }
+            }
+            Err(SignalError::ChannelClosed)
+        }
+    }
+
+    /// Returns an async stream of every signal, for use with async-std. Like
+    /// `stream`, it's fed from an unbounded channel, so it's
+    /// cancellation-safe.
+    ///
+    /// ```
+    /// use async_std::stream::StreamExt;
+    /// use signal_msg::{Signal, Signals};
+    ///
+    /// async_std::task::block_on(async {
+    ///     let signals = Signals::new().expect("couldn't install signal handlers");
+    ///     let mut stream = signals.async_std_stream();
+    ///     # signals.prime(Signal::Usr1);
+    ///     # signals.prime(Signal::Term);
+    ///     while let Some(sig) = stream.next().await {
+    ///         println!("got {}", sig);
+    ///         if sig.is_terminating() {
+    ///             break;
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    #[cfg(feature = "async-std")]
+    pub fn async_std_stream(&self) -> impl async_std::stream::Stream<Item = Signal> {
+        let (s, r) = async_std::channel::unbounded();
+        self.shared.add(Sink::AsyncStd(s));
+        r
+    }
+}
+
+/// A file descriptor that becomes readable when signals are waiting to be
+/// picked up with `drain_ready`, for registering with an event loop of one's
+/// own (`poll`, `epoll`, `mio` and so on) where the tokio and async-std
+/// features don't fit.
+///
+/// The descriptor is non-blocking, created along with the instance and
+/// owned by it, which closes it when dropped. Don't read from it: once it's readable, call
+/// `drain_ready`, which empties it and then returns what's queued. It stays
+/// readable until then, so it works with level-triggered polling; with
+/// edge-triggered polling, a signal arriving during `drain_ready` makes a
+/// new edge, so nothing is missed either. Now and then it may be readable
+/// with nothing left to drain, which is harmless.
+#[cfg(unix)]
+impl AsRawFd for Signals {
+    fn as_raw_fd(&self) -> RawFd {
+        self.ready
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| self.ready_subscriber());
+        self.ready_read.as_raw_fd()
+    }
+}
+
+impl Drop for Signals {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl Signals {
+    /// Tears this instance down like `close`, but first collects every
+    /// signal it still holds: those `recv` hasn't returned yet, those held
+    /// back by a `pause`, and any still on their way to subscribers. They're
+    /// returned in the order they were delivered. The handlers are removed
+    /// before anything else, so nothing that arrives once `drain` has
+    /// started is included or delivered.
+    pub fn drain(mut self) -> Vec<Signal> {
+        if let Some(handler) = self.shared.handler.get() {
+            platform::remove_handler(*handler);
+        }
+        let receiver = self
+            .default
+            .get_mut()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| self.subscribe());
+        // Processed before the shutdown event `close` sends, so everything
+        // lands in `receiver` first.
+        let _ = self.events.send(Event::Resume);
+        self.close();
+        receiver.collect()
+    }
+
+    /// Tears this instance down ahead of being dropped: its handlers are
+    /// removed (putting back whatever disposition each signal had before,
+    /// unless another instance still uses it), its threads are stopped and
+    /// every subscriber is disconnected. Calling it again does nothing.
+    /// With the `signalfd` feature, the signals are only unblocked in the
+    /// calling thread; see the README.
+    pub fn close(&mut self) {
+        self.shared.active.store(false, Ordering::SeqCst);
+        {
+            let handled = self.shared.handled.lock().unwrap();
+            if self.shared.chaining.swap(false, Ordering::SeqCst) {
+                platform::chain(&handled, false);
+            }
+        }
+        // Puts back whatever disposition each signal had before this
+        // instance took it over (unless another instance still uses it).
+        if let Some(handler) = self.shared.handler.get() {
+            platform::remove_handler(*handler);
+        }
+        // The dispatch thread is normally blocked waiting for the next
+        // signal, so it's woken with an explicit shutdown event. It may be
+        // waiting for a subscriber to make room instead, which it's stopped
+        // from doing first.
+        for stopper in self.shared.stoppers.lock().unwrap().drain(..) {
+            if let Some(stopper) = stopper.upgrade() {
+                stopper.stop();
+            }
+        }
+        let _ = self.events.send(Event::Shutdown);
+        if let Some(dispatcher) = self.dispatcher.take() {
+            let _ = dispatcher.join();
+        }
+        // Dropping the senders disconnects every subscriber, which ends their
+        // iterators instead of leaving them blocked forever.
+        self.shared.senders.lock().unwrap().clear();
+        // That includes the callback thread's own subscription. A callback
+        // may itself be what's dropping us, in which case joining would
+        // never return; the thread exits on its own once it's done.
+        if let Some(callback_thread) = self.callback_thread.lock().unwrap().take() {
+            if callback_thread.thread().id() != thread::current().id() {
+                let _ = callback_thread.join();
+            }
+        }
+    }
+}
+
+/// Configures a `Signals` step by step, for when `Signals::new()` and
+/// `with_signals` aren't enough:
+///
+/// ```no_run
+/// use signal_msg::{Signal, SignalsBuilder};
+///
+/// let signals = SignalsBuilder::new()
+///     .signals(&[Signal::Hup, Signal::Term])
+///     .coalesce(&[Signal::Hup])
+///     .observer(|sig| eprintln!("received {}", sig))
+///     .build()
+///     .expect("couldn't install signal handlers");
+/// ```
+pub struct SignalsBuilder {
+    signals: Vec<Signal>,
+    coalesce: Vec<Signal>,
+    chain: bool,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+    ignored: Vec<Signal>,
+    observers: Vec<Callback>,
+    #[cfg(feature = "log")]
+    log_level: log::Level,
+    #[cfg(feature = "tracing")]
+    tracing_level: tracing::Level,
+    #[cfg(unix)]
+    alt_stack: Option<usize>,
+    dispatch_thread: DispatchThread,
+}
+
+impl SignalsBuilder {
+    /// Starts from the same configuration as `Signals::new()`.
+    pub fn new() -> SignalsBuilder {
+        SignalsBuilder {
+            signals: all(),
+            coalesce: Vec::new(),
+            chain: false,
+            capacity: None,
+            overflow: OverflowPolicy::default(),
+            ignored: Vec::new(),
+            observers: Vec::new(),
+            #[cfg(feature = "log")]
+            log_level: log::Level::Info,
+            #[cfg(feature = "tracing")]
+            tracing_level: tracing::Level::INFO,
+            #[cfg(unix)]
+            alt_stack: None,
+            dispatch_thread: DispatchThread::default(),
+        }
+    }
+
+    /// The signals to handle, instead of `all()`.
+    pub fn signals(mut self, signals: &[Signal]) -> SignalsBuilder {
+        self.signals = signals.to_vec();
+        self
+    }
+
+    /// See `Signals::with_coalescing`.
+    pub fn coalesce(mut self, set: &[Signal]) -> SignalsBuilder {
+        self.coalesce = set.to_vec();
+        self
+    }
+
+    /// See `Signals::with_chaining`.
+    pub fn chaining(mut self, chain: bool) -> SignalsBuilder {
+        self.chain = chain;
+        self
+    }
+
+    /// Bounds each subscription's queue to `capacity` signals, so that a
+    /// subscriber that stops reading can't make memory grow without limit.
+    /// Subscriptions are unbounded by default.
+    ///
+    /// What happens when a subscriber's queue is full is up to
+    /// `overflow_policy`; by default, the oldest signal in it that isn't
+    /// terminating is dropped to make room. Terminating signals are never
+    /// dropped, so a shutdown request always gets through, even if that
+    /// means going over capacity. This applies to `subscribe`,
+    /// `subscribe_filtered`, `subscribe_raw`, `subscribe_info` and
+    /// `subscribe_classified`; the crossbeam, tokio and async-std channels
+    /// stay unbounded. `Signals::dropped_count` and `Signals::on_drop` tell
+    /// when signals are dropped.
+    pub fn buffer_capacity(mut self, capacity: usize) -> SignalsBuilder {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// What a subscription bounded by `buffer_capacity` does when it's
+    /// full; see `OverflowPolicy`. The default is
+    /// `OverflowPolicy::DropOldest`. Without a capacity, this does nothing.
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> SignalsBuilder {
+        self.overflow = policy;
+        self
+    }
+
+    /// See `Signals::ignore`.
+    pub fn ignore(mut self, sig: Signal) -> SignalsBuilder {
+        self.ignored.push(sig);
+        self
+    }
+
+    /// See `Signals::with_observer`; can be called more than once.
+    pub fn observer<F>(mut self, f: F) -> SignalsBuilder
+    where
+        F: Fn(Signal) + Send + 'static,
+    {
+        self.observers.push(Box::new(f));
+        self
+    }
+
+    /// The level at which received signals are logged, `Info` by default.
+    /// Signals are logged as they arrive, before `ignore` or pausing have a
+    /// say, like observers see them.
+    #[cfg(feature = "log")]
+    pub fn log_level(mut self, level: log::Level) -> SignalsBuilder {
+        self.log_level = level;
+        self
+    }
+
+    /// The level of the event recorded for each received signal, `INFO` by
+    /// default. Events are recorded as signals arrive, like they're logged
+    /// (see `log_level`), with the signal's name and number as the `signal`
+    /// and `number` fields.
+    #[cfg(feature = "tracing")]
+    pub fn tracing_level(mut self, level: tracing::Level) -> SignalsBuilder {
+        self.tracing_level = level;
+        self
+    }
+
+    /// The name of the instance's dispatch thread, the one subscribers and
+    /// observers are fed from, as shown by debuggers, profilers and
+    /// `top -H`. It's `signal-dispatch` by default. Linux only shows
+    /// the first 15 bytes of a name.
+    pub fn thread_name(mut self, name: &str) -> SignalsBuilder {
+        self.dispatch_thread.name = name.to_string();
+        self
+    }
+
+    /// Runs the dispatch thread at niceness `level` (setpriority(2)), e.g.
+    /// 10 to keep signal handling out of the way of more urgent work. If the
+    /// OS refuses, as it does for a negative level without the privilege to
+    /// raise priorities, the thread keeps running at the default priority.
+    /// Only Linux and Android can set the priority of a single thread.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn nice(mut self, level: i32) -> SignalsBuilder {
+        self.dispatch_thread.nice = Some(level);
+        self
+    }
+
+    /// Runs the signal handler on an alternate stack of `size` bytes
+    /// (sigaltstack(2)), so that it still has stack to run on when a signal
+    /// like SIGSEGV is raised because the stack overflowed. Once any
+    /// instance asks for this, handlers for every signal, this instance's
+    /// and everyone else's, are installed with `SA_ONSTACK`.
+    ///
+    /// Some caveats:
+    ///
+    /// * An alternate stack belongs to a thread, and only the thread calling
+    ///   `build` gets this one. Other threads run the handler on whatever
+    ///   alternate stack they have, if any, and otherwise on their own.
+    /// * The stack is never freed, as the thread may be running on it at
+    ///   any time for as long as it lives.
+    /// * This keeps the handler from faulting again, but it doesn't make a
+    ///   real fault survivable: once the handler returns, the faulting
+    ///   instruction runs again. A subscriber gets to hear about a SIGSEGV
+    ///   raised with `kill` or `Signal::raise`, but catching one caused by
+    ///   a bad access only makes sense alongside a crash handler, e.g. one
+    ///   reached through `chaining`, that doesn't return.
+    /// * `size` should be at least `libc::SIGSTKSZ`; the OS rejects stacks
+    ///   that are too small, and `build` fails with `AltStackFailed`.
+    /// * With the `signalfd` feature, no handler of this crate's ever runs,
+    ///   so the stack is set up but goes unused by it.
+    #[cfg(unix)]
+    pub fn alt_stack(mut self, size: usize) -> SignalsBuilder {
+        self.alt_stack = Some(size);
+        self
+    }
+
+    /// Installs the handlers. This fails like `Signals::with_signals`, e.g.
+    /// with `Uncatchable` if `Kill` or `Stop` is among the signals, with
+    /// `InvalidBufferCapacity` for a capacity of 0 and with `AltStackFailed`
+    /// if the OS refused the alternate stack; nothing is left installed in
+    /// any of these cases, and the alternate stack is only set up once
+    /// everything else has succeeded.
+    pub fn build(self) -> Result<Signals, SignalError> {
+        if self.capacity == Some(0) {
+            return Err(SignalError::InvalidBufferCapacity(0));
+        }
+        let numbers: Vec<i32> = self.signals.iter().map(Signal::to_i32).collect();
+        let signals = Signals::start(&numbers, self.dispatch_thread, true)?
+            .with_coalescing(&self.coalesce)
+            .with_chaining(self.chain);
+        if let Some(capacity) = self.capacity {
+            signals.shared.capacity.store(capacity, Ordering::SeqCst);
+        }
+        *signals.shared.overflow.lock().unwrap() = self.overflow;
+        for sig in self.ignored {
+            signals.ignore(sig);
+        }
+        #[cfg(feature = "log")]
+        {
+            *signals.shared.log_level.lock().unwrap() = self.log_level;
+        }
+        #[cfg(feature = "tracing")]
+        {
+            *signals.shared.tracing_level.lock().unwrap() = self.tracing_level;
+        }
+        signals
+            .shared
+            .observers
+            .lock()
+            .unwrap()
+            .extend(self.observers);
+        // This changes how every handler in the process is installed, so it
+        // waits until nothing else can fail; if it does fail itself,
+        // dropping `signals` takes its handlers out again.
+        #[cfg(unix)]
+        if let Some(size) = self.alt_stack {
+            platform::alt_stack(size).map_err(|errno| SignalError::AltStackFailed { errno })?;
+        }
+        Ok(signals)
+    }
+}
+
+impl Default for SignalsBuilder {
+    fn default() -> SignalsBuilder {
+        SignalsBuilder::new()
+    }
+}
+
+/// Restores the calling thread's signal mask when dropped; see
+/// `Signals::mask`.
+#[cfg(unix)]
+pub struct MaskGuard {
+    previous: platform::Mask,
+    _not_send: PhantomData<*const ()>,
+}
+
+#[cfg(unix)]
+impl Drop for MaskGuard {
+    fn drop(&mut self) {
+        platform::unblock(&self.previous);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShutdownStage {
+    Graceful,
+    Force,
+}
+
+/// Yields a `ShutdownStage` for every terminating signal (as decided by
+/// `Signal::is_terminating`, or `Signals::treat_as_terminating` where that
+/// was used); other signals are skipped. The first one is
+/// `Graceful`. Any further terminating signal that arrives within the grace
+/// period after it is `Force`; once the grace period is over, the next one
+/// counts as a fresh `Graceful` request. The iterator ends when the
+/// `Signals` it came from is dropped.
+pub struct ShutdownGuard {
+    receiver: Receiver<Signal>,
+    terminating: Overrides,
+    grace: Duration,
+    first: Option<Instant>,
+}
+
+impl Iterator for ShutdownGuard {
+    type Item = ShutdownStage;
+
+    fn next(&mut self) -> Option<ShutdownStage> {
+        loop {
+            let sig = self.receiver.recv().ok()?;
+            if !is_terminating(&self.terminating, sig) {
+                continue;
+            }
+            let now = Instant::now();
+            match self.first {
+                Some(first) if now.duration_since(first) <= self.grace => {
+                    return Some(ShutdownStage::Force)
+                }
+                _ => {
+                    self.first = Some(now);
+                    return Some(ShutdownStage::Graceful);
+                }
+            }
+        }
+    }
+}
+
+/// Installs handlers for all signals, blocks until the process is asked to
+/// shut down, then removes the handlers again and returns the signal that
+/// did it.
+///
+/// ```no_run
+/// let sig = signal_msg::wait_for_shutdown().expect("couldn't wait for signals");
+/// println!("Shutting down on {}", sig);
+/// ```
+pub fn wait_for_shutdown() -> Result<Signal, SignalError> {
+    Signals::new()?.wait_for_shutdown()
+}
+
+static CTRL_C_HANDLER_SET: AtomicBool = AtomicBool::new(false);
+
+/// Calls `f` every time SIGINT arrives (on Windows, every Ctrl-C), for
+/// programs that need nothing more, like with the `ctrlc` crate. The handler
+/// stays installed for the rest of the process; `f` runs on a thread of its
+/// own, never in the signal handler itself, so it's free to take its time or
+/// to create and drop `Signals` instances. This can only be done once: later
+/// calls fail with `HandlerAlreadySet`. A call that fails otherwise, like
+/// `Signals::with_signals` can, leaves nothing installed and can be retried.
+pub fn set_ctrl_c_handler<F>(f: F) -> Result<(), SignalError>
+where
+    F: Fn() + Send + 'static,
+{
+    if CTRL_C_HANDLER_SET.swap(true, Ordering::SeqCst) {
+        return Err(SignalError::HandlerAlreadySet);
+    }
+    // The platform layer's worker thread is shared with every `Signals`, and
+    // its callbacks run with the handler registry locked, so `f` can't run
+    // there without holding them all up.
+    let (tx, rx) = mpsc::channel();
+    let installed = platform::set_handler(&[SIGINT], move |signals| {
+        for _ in signals {
+            let _ = tx.send(());
+        }
+    });
+    match installed {
+        Ok(_) => {
+            thread::spawn(move || {
+                platform::own_thread();
+                for () in rx {
+                    f();
+                }
+            });
+            Ok(())
+        }
+        Err(err) => {
+            // Nothing was installed, so there's nothing to stop a retry.
+            CTRL_C_HANDLER_SET.store(false, Ordering::SeqCst);
+            Err(err)
+        }
+    }
+}
+
+/// Puts every signal this crate ever installed a handler for back to its
+/// default disposition (`SIG_DFL`), e.g. to give a child about to be
+/// exec'd a clean signal table. This changes process-wide state: every
+/// `Signals` instance (and `SignalSender`) stops receiving those signals,
+/// even though it stays usable otherwise, and dropping it afterwards
+/// doesn't put anything back. New instances install their handlers afresh.
+pub fn reset_all() {
+    platform::reset_all();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn receivers_iterate_until_the_instance_is_gone() {
+        let signals = Signals::without_handlers(&[Signal::Usr1]).unwrap();
+        let receiver = signals.subscribe();
+        let reader = thread::spawn(move || receiver.collect::<Vec<Signal>>());
+        signals.test_inject(Signal::Usr1);
+        drop(signals);
+        assert_eq!(reader.join().unwrap(), vec![Signal::Usr1]);
+    }
+
+    // Receives from `receiver` up to and including `last`, which tells that
+    // everything injected before it went through the fan-out.
+    fn until(receiver: &Receiver<Signal>, last: Signal) -> Vec<Signal> {
+        let mut received = Vec::new();
+        loop {
+            let sig = receiver.recv().unwrap();
+            received.push(sig);
+            if sig == last {
+                return received;
+            }
+        }
+    }
+
+    #[test]
+    fn ignored_signals_are_not_delivered() {
+        let signals = Signals::without_handlers(&[Signal::Hup, Signal::Term]).unwrap();
+        let receiver = signals.subscribe();
+        signals.ignore(Signal::Hup);
+        signals.test_inject(Signal::Hup);
+        signals.test_inject(Signal::Term);
+        assert_eq!(until(&receiver, Signal::Term), vec![Signal::Term]);
+        signals.unignore(Signal::Hup);
+        signals.test_inject(Signal::Hup);
+        assert_eq!(receiver.recv(), Ok(Signal::Hup));
+    }
+
+    #[test]
+    fn coalesced_signals_are_queued_once_per_drain() {
+        let signals = Signals::without_handlers(&[Signal::Winch, Signal::Term, Signal::Usr1])
+            .unwrap()
+            .with_coalescing(&[Signal::Winch, Signal::Term]);
+        let receiver = signals.subscribe();
+        // Tells when the fan-out is done, without reading from `receiver`
+        // while it's still going.
+        let marker = signals.subscribe();
+        for _ in 0..100 {
+            signals.test_inject(Signal::Winch);
+        }
+        // Terminating signals are never coalesced, even when asked to be.
+        signals.test_inject(Signal::Term);
+        signals.test_inject(Signal::Term);
+        signals.test_inject(Signal::Usr1);
+        until(&marker, Signal::Usr1);
+        assert_eq!(
+            receiver.drain_available(),
+            vec![Signal::Winch, Signal::Term, Signal::Term, Signal::Usr1]
+        );
+        // Once the first one has been read, the next one is queued again.
+        signals.test_inject(Signal::Winch);
+        signals.test_inject(Signal::Winch);
+        signals.test_inject(Signal::Usr1);
+        until(&marker, Signal::Usr1);
+        assert_eq!(
+            receiver.drain_available(),
+            vec![Signal::Winch, Signal::Usr1]
+        );
+    }
+
+    #[test]
+    fn concurrent_subscribers_all_receive() {
+        let signals = Signals::without_handlers(&[Signal::Usr1]).unwrap();
+        let receivers: Vec<Receiver<Signal>> = thread::scope(|scope| {
+            let subscribing: Vec<_> = (0..16)
+                .map(|_| scope.spawn(|| signals.subscribe()))
+                .collect();
+            subscribing.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+        signals.test_inject(Signal::Usr1);
+        for receiver in receivers {
+            assert_eq!(receiver.recv(), Ok(Signal::Usr1));
+        }
+    }
+
+    #[test]
+    fn dropped_subscriptions_are_unregistered() {
+        let signals = Signals::without_handlers(&[Signal::Usr1]).unwrap();
+        let baseline = signals.shared.senders.lock().unwrap().len();
+        let receivers: Vec<Receiver<Signal>> = (0..100).map(|_| signals.subscribe()).collect();
+        assert_eq!(signals.shared.senders.lock().unwrap().len(), baseline + 100);
+        drop(receivers);
+        for _ in 0..100 {
+            drop(signals.subscribe_filtered(&[Signal::Usr1]));
+            drop(signals.subscribe_raw());
+        }
+        assert_eq!(signals.shared.senders.lock().unwrap().len(), baseline);
+    }
+
+    #[test]
+    fn callbacks_run_for_their_signal() {
+        let mut signals = Signals::without_handlers(&[Signal::Usr1, Signal::Usr2]).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        signals.on(Signal::Usr1, move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Usr2);
+        signals.test_inject(Signal::Usr1);
+        // Waits for the callback thread to get through everything.
+        signals.close();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn a_second_terminating_signal_within_the_grace_period_forces() {
+        let signals =
+            Signals::without_handlers(&[Signal::Int, Signal::Term, Signal::Usr1]).unwrap();
+        let mut guard = signals.shutdown_guard(Duration::from_secs(60));
+        signals.test_inject(Signal::Int);
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Term);
+        assert_eq!(guard.next(), Some(ShutdownStage::Graceful));
+        assert_eq!(guard.next(), Some(ShutdownStage::Force));
+        drop(signals);
+        assert_eq!(guard.next(), None);
+    }
+
+    #[test]
+    fn a_terminating_signal_after_the_grace_period_starts_over() {
+        let signals = Signals::without_handlers(&[Signal::Term]).unwrap();
+        let mut guard = signals.shutdown_guard(Duration::from_millis(10));
+        signals.test_inject(Signal::Term);
+        assert_eq!(guard.next(), Some(ShutdownStage::Graceful));
+        thread::sleep(Duration::from_millis(50));
+        signals.test_inject(Signal::Term);
+        assert_eq!(guard.next(), Some(ShutdownStage::Graceful));
+    }
+
+    // Waits until `signals` has `n` subscribers, for tests whose subscriber
+    // is created by the call under test on another thread.
+    fn wait_for_subscribers(signals: &Signals, n: usize) {
+        while signals.shared.senders.lock().unwrap().len() < n {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn wait_for_skips_signals_outside_the_set() {
+        let signals = Signals::without_handlers(&[Signal::Usr1, Signal::Usr2]).unwrap();
+        thread::scope(|scope| {
+            let waiting = scope.spawn(|| signals.wait_for(&[Signal::Usr2]));
+            wait_for_subscribers(&signals, 1);
+            signals.test_inject(Signal::Usr1);
+            signals.test_inject(Signal::Usr2);
+            assert_eq!(waiting.join().unwrap(), Ok(Signal::Usr2));
+        });
+    }
+
+    #[test]
+    fn pending_counts_queued_signals() {
+        let signals = Signals::without_handlers(&[Signal::Usr1, Signal::Usr2]).unwrap();
+        let receiver = signals.subscribe();
+        let marker = signals.subscribe();
+        assert_eq!(receiver.pending(), 0);
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Usr2);
+        until(&marker, Signal::Usr2);
+        assert_eq!(receiver.pending(), 3);
+        receiver.recv().unwrap();
+        assert_eq!(receiver.pending(), 2);
+    }
+
+    #[test]
+    fn handled_lists_the_requested_signals() {
+        let requested = [Signal::Hup, Signal::Usr1, Signal::Winch];
+        let signals = Signals::without_handlers(&requested).unwrap();
+        assert_eq!(signals.handled(), requested);
+        signals.ignore(Signal::Usr1);
+        assert_eq!(signals.handled(), [Signal::Hup, Signal::Winch]);
+    }
+
+    #[test]
+    fn filtered_subscribers_only_receive_their_signals() {
+        let handled = [Signal::Hup, Signal::Usr1, Signal::Usr2, Signal::Winch];
+        let signals = Signals::without_handlers(&handled).unwrap();
+        let first = signals.subscribe_filtered(&[Signal::Hup, Signal::Usr1]);
+        let second = signals.subscribe_filtered(&[Signal::Usr2]);
+        let marker = signals.subscribe();
+        for sig in [Signal::Usr1, Signal::Usr2, Signal::Hup, Signal::Usr2] {
+            signals.test_inject(sig);
+        }
+        signals.test_inject(Signal::Winch);
+        until(&marker, Signal::Winch);
+        assert_eq!(first.drain_available(), [Signal::Usr1, Signal::Hup]);
+        assert_eq!(second.drain_available(), [Signal::Usr2, Signal::Usr2]);
+    }
+
+    #[test]
+    fn observers_see_signals_nobody_subscribed_to() {
+        let signals = Signals::without_handlers(&[Signal::Hup, Signal::Usr1]).unwrap();
+        let (seen, observed) = mpsc::channel();
+        signals.with_observer(move |sig| {
+            let _ = seen.send(sig);
+        });
+        let _filtered = signals.subscribe_filtered(&[Signal::Usr1]);
+        signals.ignore(Signal::Hup);
+        signals.test_inject(Signal::Hup);
+        assert_eq!(observed.recv(), Ok(Signal::Hup));
+    }
+
+    #[test]
+    fn recv_fails_once_the_instance_is_closed() {
+        fn next(signals: &Signals) -> Result<Signal, Box<dyn std::error::Error>> {
+            Ok(signals.recv()?)
+        }
+
+        let mut signals = Signals::without_handlers(&[Signal::Usr1]).unwrap();
+        // The first call creates the subscriber, so inject from another
+        // thread once it's there.
+        thread::scope(|scope| {
+            let receiving = scope.spawn(|| next(&signals).unwrap());
+            wait_for_subscribers(&signals, 1);
+            signals.test_inject(Signal::Usr1);
+            assert_eq!(receiving.join().unwrap(), Signal::Usr1);
+        });
+        signals.close();
+        assert_eq!(signals.recv(), Err(SignalError::ChannelClosed));
+        let err = next(&signals).unwrap_err();
+        assert_eq!(err.to_string(), SignalError::ChannelClosed.to_string());
+    }
+
+    #[test]
+    fn uncatchable_signals_are_refused() {
+        // These fail before anything is installed, so they're safe to try
+        // for real here.
+        assert_eq!(
+            Signals::with_signals(&[Signal::Term, Signal::Kill]).err(),
+            Some(SignalError::Uncatchable(Signal::Kill))
+        );
+        assert_eq!(
+            Signals::with_signals(&[Signal::Stop]).err(),
+            Some(SignalError::Uncatchable(Signal::Stop))
+        );
+        assert_eq!(
+            Signals::without_handlers(&[Signal::Kill]).err(),
+            Some(SignalError::Uncatchable(Signal::Kill))
+        );
+    }
+
+    // An instance whose subscriptions are bounded to `capacity`, as
+    // `SignalsBuilder::buffer_capacity` would make it.
+    fn bounded(signals: &[Signal], capacity: usize, policy: OverflowPolicy) -> Signals {
+        let signals = Signals::without_handlers(signals).unwrap();
+        signals.shared.capacity.store(capacity, Ordering::SeqCst);
+        *signals.shared.overflow.lock().unwrap() = policy;
+        signals
+    }
+
+    #[test]
+    fn stalled_subscribers_stay_bounded_but_get_terminating_signals() {
+        let signals = bounded(&[Signal::Usr1, Signal::Term], 4, OverflowPolicy::DropOldest);
+        let stalled = signals.subscribe();
+        let marker = signals.subscribe();
+        for _ in 0..1000 {
+            signals.test_inject(Signal::Usr1);
+        }
+        signals.test_inject(Signal::Term);
+        until(&marker, Signal::Term);
+        // The oldest ones made room, SIGTERM included.
+        assert_eq!(stalled.pending(), 4);
+        assert_eq!(
+            stalled.drain_available(),
+            [Signal::Usr1, Signal::Usr1, Signal::Usr1, Signal::Term]
+        );
+        // The marker, being read, may have dropped some too.
+        assert!(signals.dropped_count() >= 997);
+    }
+
+    #[test]
+    fn drop_newest_keeps_what_was_queued() {
+        let signals = bounded(
+            &[Signal::Usr1, Signal::Usr2, Signal::Winch],
+            2,
+            OverflowPolicy::DropNewest,
+        );
+        let stalled = signals.subscribe();
+        let marker = signals.subscribe_filtered(&[Signal::Winch]);
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Usr2);
+        signals.test_inject(Signal::Winch);
+        until(&marker, Signal::Winch);
+        assert_eq!(stalled.drain_available(), [Signal::Usr1, Signal::Usr2]);
+    }
+
+    #[test]
+    fn block_waits_for_room_instead_of_dropping() {
+        let signals = bounded(&[Signal::Usr1, Signal::Usr2], 2, OverflowPolicy::Block);
+        let receiver = signals.subscribe();
+        let injected: Vec<Signal> = (0..10)
+            .map(|n| {
+                if n % 2 == 0 {
+                    Signal::Usr1
+                } else {
+                    Signal::Usr2
+                }
+            })
+            .collect();
+        for sig in &injected {
+            signals.test_inject(*sig);
+        }
+        let received: Vec<Signal> = (0..10).map(|_| receiver.recv().unwrap()).collect();
+        assert_eq!(received, injected);
+        assert_eq!(signals.dropped_count(), 0);
+    }
+
+    #[test]
+    fn instances_are_inactive_once_closed() {
+        let mut signals = Signals::without_handlers(&[Signal::Usr1]).unwrap();
+        assert!(signals.is_active());
+        signals.close();
+        assert!(!signals.is_active());
+        signals.close();
+        assert!(!signals.is_active());
+    }
+
+    #[test]
+    fn resubscribing_keeps_the_filter() {
+        let mut signals = Signals::without_handlers(&[Signal::Usr1, Signal::Usr2]).unwrap();
+        let receiver = signals.subscribe_filtered(&[Signal::Usr1]);
+        signals.test_inject(Signal::Usr1);
+        assert_eq!(receiver.recv(), Ok(Signal::Usr1));
+        let rearmed = receiver.resubscribe();
+        drop(receiver);
+        signals.test_inject(Signal::Usr2);
+        signals.test_inject(Signal::Usr1);
+        assert_eq!(rearmed.recv(), Ok(Signal::Usr1));
+        signals.close();
+        assert_eq!(rearmed.recv(), Err(SignalError::ChannelClosed));
+        assert_eq!(
+            rearmed.resubscribe().recv(),
+            Err(SignalError::ChannelClosed)
+        );
+        drop(signals);
+        assert_eq!(
+            rearmed.resubscribe().recv(),
+            Err(SignalError::ChannelClosed)
+        );
+    }
+
+    #[test]
+    fn primed_signals_reach_subscribers() {
+        let signals = Signals::without_handlers(&[Signal::Hup]).unwrap();
+        let (seen, observed) = mpsc::channel();
+        signals.with_observer(move |sig| {
+            let _ = seen.send(sig);
+        });
+        let receiver = signals.subscribe();
+        signals.prime(Signal::Hup);
+        assert_eq!(receiver.recv(), Ok(Signal::Hup));
+        assert_eq!(observed.recv(), Ok(Signal::Hup));
+        assert_eq!(signals.last(), Some(Signal::Hup));
+    }
+
+    #[test]
+    fn drain_returns_everything_still_held() {
+        let signals =
+            Signals::without_handlers(&[Signal::Usr1, Signal::Usr2, Signal::Winch]).unwrap();
+        signals.pause();
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Usr2);
+        signals.test_inject(Signal::Winch);
+        assert_eq!(signals.drain(), [Signal::Usr1, Signal::Usr2, Signal::Winch]);
+    }
+
+    #[test]
+    fn cancelling_wakes_a_blocked_recv() {
+        let signals = Signals::without_handlers(&[Signal::Usr1]).unwrap();
+        let (receiver, cancel) = signals.subscribe_cancellable();
+        let blocked = thread::spawn(move || receiver.recv());
+        thread::sleep(Duration::from_millis(20));
+        let cancelling = thread::spawn(move || cancel.cancel());
+        cancelling.join().unwrap();
+        assert_eq!(blocked.join().unwrap(), Err(SignalError::ChannelClosed));
+        // The instance itself carries on.
+        let receiver = signals.subscribe();
+        signals.test_inject(Signal::Usr1);
+        assert_eq!(receiver.recv(), Ok(Signal::Usr1));
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn received_signals_are_logged() {
+        // Loggers are process-wide, so this is the only test that sets one,
+        // and it only looks at SIGCONT, which no other test uses.
+        struct Capture(Mutex<Vec<(log::Level, String)>>);
+
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                let message = record.args().to_string();
+                self.0.lock().unwrap().push((record.level(), message));
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+        let signals = Signals::without_handlers(&[Signal::Cont]).unwrap();
+        *signals.shared.log_level.lock().unwrap() = log::Level::Warn;
+        let receiver = signals.subscribe();
+        signals.test_inject(Signal::Cont);
+        receiver.recv().unwrap();
+        let records = LOGGER.0.lock().unwrap();
+        let logged: Vec<_> = records
+            .iter()
+            .filter(|(_, message)| message.contains("SIGCONT"))
+            .collect();
+        assert_eq!(
+            logged,
+            [&(log::Level::Warn, "received SIGCONT".to_string())]
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn the_shutdown_future_fails_once_the_instance_is_closed() {
+        let mut signals = Signals::without_handlers(&[Signal::Term]).unwrap();
+        let shutdown = signals.shutdown_signal();
+        signals.close();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        assert_eq!(runtime.block_on(shutdown), Err(SignalError::ChannelClosed));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn current_mask_reports_blocked_signals() {
+        // Masks are per thread, so this one is all the test's own.
+        let signals = Signals::without_handlers(&[Signal::Usr2]).unwrap();
+        assert!(!Signals::current_mask().contains(&Signal::Usr2));
+        let guard = signals.mask(&[Signal::Usr2]);
+        assert!(Signals::current_mask().contains(&Signal::Usr2));
+        drop(guard);
+        assert!(!Signals::current_mask().contains(&Signal::Usr2));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn received_signals_are_traced() {
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        // Events are recorded on the dispatch thread, so the subscriber has
+        // to be the global one; this is the only test that sets it, and it
+        // only looks at SIGTSTP, which no other test uses.
+        static EVENTS: Mutex<Vec<(tracing::Level, String)>> = Mutex::new(Vec::new());
+
+        struct Capture;
+
+        struct SignalField(Option<String>);
+
+        impl Visit for SignalField {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "signal" {
+                    self.0 = Some(format!("{:?}", value));
+                }
+            }
+        }
+
+        impl tracing::Subscriber for Capture {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut signal = SignalField(None);
+                event.record(&mut signal);
+                if let Some(signal) = signal.0 {
+                    let level = *event.metadata().level();
+                    EVENTS.lock().unwrap().push((level, signal));
+                }
+            }
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        tracing::subscriber::set_global_default(Capture).unwrap();
+        let signals = Signals::without_handlers(&[Signal::Tstp]).unwrap();
+        *signals.shared.tracing_level.lock().unwrap() = tracing::Level::WARN;
+        let receiver = signals.subscribe();
+        signals.test_inject(Signal::Tstp);
+        receiver.recv().unwrap();
+        let events = EVENTS.lock().unwrap();
+        let traced: Vec<_> = events.iter().filter(|(_, s)| s == "SIGTSTP").collect();
+        assert_eq!(traced, [&(tracing::Level::WARN, "SIGTSTP".to_string())]);
+    }
+
+    #[test]
+    fn run_stops_when_the_handler_breaks() {
+        let signals = Signals::without_handlers(&[Signal::Usr1]).unwrap();
+        thread::scope(|scope| {
+            let running = scope.spawn(|| {
+                let mut calls = 0;
+                signals.run(|_| {
+                    calls += 1;
+                    if calls == 2 {
+                        ControlFlow::Break(())
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                });
+                calls
+            });
+            wait_for_subscribers(&signals, 1);
+            for _ in 0..3 {
+                signals.test_inject(Signal::Usr1);
+            }
+            assert_eq!(running.join().unwrap(), 2);
+        });
+    }
+
+    #[test]
+    fn the_dispatch_thread_is_set_up_as_configured() {
+        let config = DispatchThread {
+            name: String::from("custom-dispatch"),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            nice: Some(5),
+        };
+        let signals = Signals::start(&[Signal::Usr1.to_i32()], config, false).unwrap();
+        let (seen, observed) = mpsc::channel();
+        signals.with_observer(move |_| {
+            let name = thread::current().name().map(String::from);
+            // On Linux, this is the calling thread's niceness.
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            let nice = Some(unsafe { libc::getpriority(libc::PRIO_PROCESS, 0) });
+            #[cfg(not(any(target_os = "linux", target_os = "android")))]
+            let nice: Option<i32> = None;
+            let _ = seen.send((name, nice));
+        });
+        signals.test_inject(Signal::Usr1);
+        let (name, nice) = observed.recv().unwrap();
+        assert_eq!(name.as_deref(), Some("custom-dispatch"));
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(nice, Some(5));
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        assert_eq!(nice, None);
+    }
+
+    #[test]
+    fn injected_signals_arrive_in_order_without_os_handlers() {
+        let signals =
+            Signals::without_handlers(&[Signal::Int, Signal::Term, Signal::Usr1]).unwrap();
+        assert!(signals.shared.handler.get().is_none());
+        let receiver = signals.subscribe();
+        let injected = [Signal::Usr1, Signal::Int, Signal::Usr1, Signal::Term];
+        for sig in injected {
+            signals.test_inject(sig);
+        }
+        assert_eq!(until(&receiver, Signal::Term), injected);
+        assert_eq!(signals.counts().get(&Signal::Usr1), Some(&2));
+    }
+
+    #[test]
+    fn wait_for_timeout_matches_or_times_out() {
+        let signals = Signals::without_handlers(&[Signal::Usr1, Signal::Usr2]).unwrap();
+        assert_eq!(
+            signals.wait_for_timeout(&[Signal::Usr1], Duration::from_millis(20)),
+            Ok(None)
+        );
+        thread::scope(|scope| {
+            let waiting = scope.spawn(|| signals.wait_for_timeout(&[Signal::Usr1], TIMEOUT));
+            wait_for_subscribers(&signals, 1);
+            signals.test_inject(Signal::Usr1);
+            assert_eq!(waiting.join().unwrap(), Ok(Some(Signal::Usr1)));
+        });
+        // Signals outside the set are skipped, until one in it arrives.
+        thread::scope(|scope| {
+            let waiting = scope.spawn(|| signals.wait_for_timeout(&[Signal::Usr2], TIMEOUT));
+            wait_for_subscribers(&signals, 1);
+            signals.test_inject(Signal::Usr1);
+            signals.test_inject(Signal::Usr2);
+            assert_eq!(waiting.join().unwrap(), Ok(Some(Signal::Usr2)));
+        });
+    }
+
+    #[test]
+    fn classified_signals_match_the_standalone_methods() {
+        let sample = [Signal::Hup, Signal::Term, Signal::Usr1, Signal::Winch];
+        let signals = Signals::without_handlers(&sample).unwrap();
+        let receiver = signals.subscribe_classified();
+        for sig in sample {
+            signals.test_inject(sig);
+        }
+        for sig in sample {
+            let classified = receiver.recv().unwrap();
+            assert_eq!(classified.signal, sig);
+            assert_eq!(classified.action, sig.default_action());
+            assert_eq!(classified.terminating, sig.is_terminating());
+        }
+    }
+
+    #[test]
+    fn signals_marked_terminating_end_wait_for_shutdown() {
+        let signals =
+            Signals::without_handlers(&[Signal::Hup, Signal::Usr1, Signal::Winch]).unwrap();
+        signals.treat_as_terminating(Signal::Usr1, true);
+        signals.treat_as_terminating(Signal::Hup, false);
+        thread::scope(|scope| {
+            let waiting = scope.spawn(|| signals.wait_for_shutdown());
+            wait_for_subscribers(&signals, 1);
+            signals.test_inject(Signal::Winch);
+            signals.test_inject(Signal::Hup);
+            signals.test_inject(Signal::Usr1);
+            assert_eq!(waiting.join().unwrap(), Ok(Signal::Usr1));
+        });
+        // Only this instance's helpers are affected.
+        assert!(!Signal::Usr1.is_terminating());
+    }
+
+    #[test]
+    fn drain_available_takes_everything_queued() {
+        let signals =
+            Signals::without_handlers(&[Signal::Usr1, Signal::Usr2, Signal::Winch]).unwrap();
+        let receiver = signals.subscribe();
+        let marker = signals.subscribe();
+        assert_eq!(receiver.drain_available(), []);
+        for sig in [Signal::Usr1, Signal::Usr2, Signal::Winch] {
+            signals.test_inject(sig);
+        }
+        until(&marker, Signal::Winch);
+        assert_eq!(
+            receiver.drain_available(),
+            [Signal::Usr1, Signal::Usr2, Signal::Winch]
+        );
+        assert_eq!(receiver.pending(), 0);
+    }
+
+    #[test]
+    fn dropped_signals_are_counted_and_reported() {
+        let signals = bounded(
+            &[Signal::Usr1, Signal::Usr2, Signal::Hup],
+            2,
+            OverflowPolicy::DropOldest,
+        );
+        let (report, reported) = mpsc::channel();
+        signals.on_drop(move |sig| {
+            let _ = report.send(sig);
+        });
+        let (observe, observed) = mpsc::channel();
+        signals.with_observer(move |sig| {
+            let _ = observe.send(sig);
+        });
+        let _stalled = signals.subscribe_filtered(&[Signal::Usr1, Signal::Usr2]);
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Usr2);
+        // The dispatch thread only gets to SIGHUP, which the subscriber
+        // doesn't take, once it's done with the others.
+        signals.test_inject(Signal::Hup);
+        while observed.recv() != Ok(Signal::Hup) {}
+        assert_eq!(signals.dropped_count(), 0);
+        // The stalled subscriber's queue is full, so the oldest goes.
+        signals.test_inject(Signal::Usr2);
+        assert_eq!(reported.recv(), Ok(Signal::Usr1));
+        assert_eq!(signals.dropped_count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn forwarding_starts_with_signals_received_after_the_call() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Command;
+
+        let signals = Signals::without_handlers(&[Signal::Hup, Signal::Usr1]).unwrap();
+        let receiver = signals.subscribe();
+        signals.prime(Signal::Hup);
+        assert_eq!(receiver.recv(), Ok(Signal::Hup));
+        let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+        signals.forward_to(&child, &[Signal::Hup, Signal::Usr1]);
+        signals.prime(Signal::Usr1);
+        // A replayed SIGHUP would have been forwarded, and killed it, first.
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGUSR1));
+    }
+
+    #[test]
+    fn wait_count_only_counts_signals_after_the_call() {
+        let signals = Signals::without_handlers(&[Signal::Term]).unwrap();
+        let marker = signals.subscribe();
+        signals.test_inject(Signal::Term);
+        assert_eq!(marker.recv(), Ok(Signal::Term));
+        let (done, finished) = mpsc::channel();
+        thread::scope(|scope| {
+            scope.spawn(|| done.send(signals.wait_count(Signal::Term, 1)).unwrap());
+            wait_for_subscribers(&signals, 2);
+            assert_eq!(
+                finished.recv_timeout(Duration::from_millis(50)),
+                Err(mpsc::RecvTimeoutError::Timeout)
+            );
+            signals.test_inject(Signal::Term);
+            assert_eq!(finished.recv_timeout(TIMEOUT), Ok(Ok(())));
+        });
+    }
+
+    #[test]
+    fn wait_for_doesnt_return_an_earlier_signal() {
+        let signals = Signals::without_handlers(&[Signal::Term]).unwrap();
+        let marker = signals.subscribe();
+        signals.test_inject(Signal::Term);
+        assert_eq!(marker.recv(), Ok(Signal::Term));
+        let (done, finished) = mpsc::channel();
+        thread::scope(|scope| {
+            scope.spawn(|| done.send(signals.wait_for(&[Signal::Term])).unwrap());
+            wait_for_subscribers(&signals, 2);
+            assert_eq!(
+                finished.recv_timeout(Duration::from_millis(50)),
+                Err(mpsc::RecvTimeoutError::Timeout)
+            );
+            signals.test_inject(Signal::Term);
+            assert_eq!(finished.recv_timeout(TIMEOUT), Ok(Ok(Signal::Term)));
+        });
+    }
+
+    #[test]
+    fn wait_for_timeout_doesnt_return_an_earlier_signal() {
+        let signals = Signals::without_handlers(&[Signal::Term]).unwrap();
+        let marker = signals.subscribe();
+        signals.test_inject(Signal::Term);
+        assert_eq!(marker.recv(), Ok(Signal::Term));
+        assert_eq!(
+            signals.wait_for_timeout(&[Signal::Term], Duration::from_millis(50)),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn run_doesnt_start_with_an_earlier_signal() {
+        let signals = Signals::without_handlers(&[Signal::Usr1, Signal::Term]).unwrap();
+        let marker = signals.subscribe();
+        signals.test_inject(Signal::Term);
+        assert_eq!(marker.recv(), Ok(Signal::Term));
+        let received = thread::scope(|scope| {
+            let running = scope.spawn(|| {
+                let mut received = Vec::new();
+                signals.run(|sig| {
+                    received.push(sig);
+                    ControlFlow::Break(())
+                });
+                received
+            });
+            wait_for_subscribers(&signals, 2);
+            signals.test_inject(Signal::Usr1);
+            running.join().unwrap()
+        });
+        assert_eq!(received, vec![Signal::Usr1]);
+    }
+
+    #[test]
+    fn priming_an_unhandled_signal_does_nothing() {
+        let signals = Signals::without_handlers(&[Signal::Usr1]).unwrap();
+        let receiver = signals.subscribe();
+        signals.prime(Signal::Usr2);
+        signals.prime(Signal::Usr1);
+        assert_eq!(until(&receiver, Signal::Usr1), vec![Signal::Usr1]);
+        assert_eq!(signals.counts().get(&Signal::Usr2), None);
+    }
+
+    #[test]
+    fn signals_treated_as_terminating_are_never_dropped() {
+        let signals = bounded(&[Signal::Usr1, Signal::Hup], 1, OverflowPolicy::DropNewest);
+        signals.treat_as_terminating(Signal::Hup, true);
+        let stalled = signals.subscribe();
+        let marker = signals.subscribe_filtered(&[Signal::Hup]);
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Hup);
+        assert_eq!(marker.recv(), Ok(Signal::Hup));
+        assert_eq!(stalled.drain_available(), [Signal::Usr1, Signal::Hup]);
+        assert_eq!(signals.dropped_count(), 0);
+        assert_eq!(signals.wait_for_shutdown(), Ok(Signal::Hup));
+    }
+
+    #[test]
+    fn signals_treated_as_terminating_skip_pauses_and_coalescing() {
+        let signals = Signals::without_handlers(&[Signal::Usr1, Signal::Hup])
+            .unwrap()
+            .with_coalescing(&[Signal::Hup]);
+        signals.treat_as_terminating(Signal::Hup, true);
+        let receiver = signals.subscribe();
+        signals.pause();
+        signals.test_inject(Signal::Usr1);
+        signals.test_inject(Signal::Hup);
+        signals.test_inject(Signal::Hup);
+        signals.resume();
+        assert_eq!(
+            until(&receiver, Signal::Usr1),
+            vec![Signal::Hup, Signal::Hup, Signal::Usr1]
+        );
+    }
+}
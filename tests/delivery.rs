@@ -0,0 +1,665 @@
+// Tests that go through real signal delivery: they raise signals at this
+// process and check what arrives. Handlers and dispositions are
+// process-wide, so the tests here take turns (see `serial`) instead of
+// running in parallel.
+//
+// The legacy API installs handlers for good, so its tests stick to SIGWINCH,
+// which nothing else here uses and which is harmless to leave handled.
+//
+// The signalfd backend needs the signals blocked before any thread starts,
+// which the test harness doesn't allow for, so it has tests of its own, in
+// signalfd.rs.
+#![cfg(all(
+    unix,
+    feature = "std",
+    not(all(target_os = "linux", feature = "signalfd"))
+))]
+
+use signal_msg::{OverflowPolicy, Signal, SignalError, SignalSender, Signals, SignalsBuilder};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex, MutexGuard};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn serial() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    // A failed test poisons the lock, which mustn't fail the others too.
+    LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+// The handler currently installed for `sig`, as sigaction(2) reports it.
+fn disposition(sig: Signal) -> libc::sighandler_t {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        assert_eq!(
+            libc::sigaction(sig.to_i32(), std::ptr::null(), &mut action),
+            0
+        );
+        action.sa_sigaction
+    }
+}
+
+// Runs the test `name` again in a process of its own, for tests that need
+// the process to themselves, e.g. to be the first to set something up.
+// Returns true in that process, where the test goes on, and false in this
+// one once the other has passed.
+fn run_alone(name: &str) -> bool {
+    const CHILD: &str = "SIGNAL_MSG_TEST_CHILD";
+    if std::env::var_os(CHILD).is_some() {
+        return true;
+    }
+    let _serial = serial();
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([name, "--exact"])
+        .env(CHILD, "1")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    false
+}
+
+// The thread id of the thread that reads the signal pipe.
+#[cfg(target_os = "linux")]
+fn worker_thread() -> libc::pid_t {
+    // The worker names itself once it's running, which may take a moment.
+    loop {
+        let worker = std::fs::read_dir("/proc/self/task")
+            .unwrap()
+            .map(|task| task.unwrap().path())
+            .find(|task| std::fs::read_to_string(task.join("comm")).unwrap() == "signal-msg\n");
+        if let Some(worker) = worker {
+            return worker
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn legacy_sender_survives_a_dropped_receiver() {
+    let _serial = serial();
+    let (sender, receiver) = signal_msg::new();
+    drop(receiver);
+    sender.prepare_signals_for(&[Signal::Winch]);
+    Signal::Winch.raise().unwrap();
+    // Both go through the same worker thread, so this one only arrives if
+    // sending to the dropped receiver didn't take it down.
+    let signals = Signals::with_signals(&[Signal::Winch]).unwrap();
+    let receiver = signals.subscribe();
+    Signal::Winch.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Winch)));
+}
+
+#[test]
+fn signals_outside_the_requested_set_are_not_delivered() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    let receiver = signals.subscribe();
+    // Someone has to handle SIGUSR2, or raising it would end the test run.
+    let other = Signals::with_signals(&[Signal::Usr2]).unwrap();
+    let other_receiver = other.subscribe();
+    Signal::Usr2.raise().unwrap();
+    assert_eq!(other_receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr2)));
+    Signal::Usr1.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr1)));
+    assert_eq!(receiver.try_recv(), Ok(None));
+}
+
+#[test]
+fn dropping_the_instance_restores_the_default_disposition() {
+    let _serial = serial();
+    assert_eq!(disposition(Signal::Usr1), libc::SIG_DFL);
+    let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    assert_ne!(disposition(Signal::Usr1), libc::SIG_DFL);
+    drop(signals);
+    assert_eq!(disposition(Signal::Usr1), libc::SIG_DFL);
+}
+
+#[test]
+fn sigpipe_is_not_intercepted_by_default() {
+    let _serial = serial();
+    // Rust programs start with SIGPIPE ignored, so that's what must stay.
+    let before = disposition(Signal::Pipe);
+    let signals = Signals::new().unwrap();
+    assert!(!signals.handled().contains(&Signal::Pipe));
+    assert_eq!(disposition(Signal::Pipe), before);
+}
+
+#[test]
+fn legacy_signal_channels_receive_variants() {
+    let _serial = serial();
+    let (sender, receiver) = mpsc::channel::<Signal>();
+    sender.prepare_signals_for(&[Signal::Winch]);
+    Signal::Winch.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Signal::Winch));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn dropped_instances_stop_their_threads() {
+    let _serial = serial();
+    let threads = || std::fs::read_dir("/proc/self/task").unwrap().count();
+    // The first instance starts the shared worker thread, which stays.
+    drop(Signals::with_signals(&[Signal::Usr1]).unwrap());
+    let before = threads();
+    for _ in 0..50 {
+        let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+        signals.on(Signal::Usr1, |_| {});
+        drop(signals);
+    }
+    // Leaves some slack for the test harness starting threads of its own.
+    assert!(
+        threads() <= before + 2,
+        "{} threads, {} before",
+        threads(),
+        before
+    );
+}
+
+#[test]
+fn signals_during_allocation_dont_deadlock() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1, Signal::Usr2]).unwrap();
+    let receiver = signals.subscribe();
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    let words: Vec<String> = (0..64).map(|n| n.to_string()).collect();
+                    drop(words);
+                }
+            });
+        }
+        // Sent to the process rather than raised, so they can land in any
+        // of the threads, most likely in the middle of malloc or free.
+        for _ in 0..1000 {
+            let words: Vec<String> = (0..64).map(|n| n.to_string()).collect();
+            drop(words);
+            unsafe { libc::kill(libc::getpid(), libc::SIGUSR1) };
+        }
+        unsafe { libc::kill(libc::getpid(), libc::SIGUSR2) };
+        let last = loop {
+            match receiver.recv_timeout(TIMEOUT) {
+                Ok(Some(Signal::Usr1)) => {}
+                other => break other,
+            }
+        };
+        stop.store(true, Ordering::Relaxed);
+        assert_eq!(last, Ok(Some(Signal::Usr2)));
+    });
+}
+
+#[test]
+fn masked_signals_arrive_once_the_guard_is_dropped() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Term]).unwrap();
+    let receiver = signals.subscribe();
+    let guard = signals.mask(&[Signal::Term]);
+    Signal::Term.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(Duration::from_millis(100)), Ok(None));
+    drop(guard);
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Term)));
+}
+
+#[test]
+fn reset_all_restores_the_default_dispositions() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr2]).unwrap();
+    let (sender, _receiver) = signal_msg::new();
+    sender.prepare_signals_for(&[Signal::Winch]);
+    assert_ne!(disposition(Signal::Usr2), libc::SIG_DFL);
+    assert_ne!(disposition(Signal::Winch), libc::SIG_DFL);
+    signal_msg::reset_all();
+    assert_eq!(disposition(Signal::Usr2), libc::SIG_DFL);
+    assert_eq!(disposition(Signal::Winch), libc::SIG_DFL);
+    // Nothing is put back when the instance goes away afterwards.
+    drop(signals);
+    assert_eq!(disposition(Signal::Usr2), libc::SIG_DFL);
+}
+
+#[test]
+fn signals_from_this_process_say_so() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    let receiver = signals.subscribe_info();
+    Signal::Usr1.raise().unwrap();
+    let info = receiver.recv_timeout(TIMEOUT).unwrap().unwrap();
+    assert_eq!(info.signal, Signal::Usr1);
+    assert_eq!(info.sender_pid, Some(std::process::id() as i32));
+    assert_eq!(info.sender_uid, Some(unsafe { libc::getuid() }));
+}
+
+#[test]
+fn the_builder_applies_every_option() {
+    let _serial = serial();
+    let (seen, observed) = mpsc::channel();
+    let mut signals = SignalsBuilder::new()
+        .signals(&[Signal::Usr1, Signal::Usr2, Signal::Winch])
+        .coalesce(&[Signal::Winch])
+        .ignore(Signal::Usr2)
+        .buffer_capacity(2)
+        .overflow_policy(OverflowPolicy::DropNewest)
+        .observer(move |sig| {
+            let thread = std::thread::current().name().map(String::from);
+            let _ = seen.send((sig, thread));
+        })
+        .thread_name("builder-test")
+        .chaining(true)
+        .build()
+        .unwrap();
+    assert_eq!(signals.handled(), [Signal::Usr1, Signal::Winch]);
+    let receiver = signals.subscribe();
+    for sig in [
+        Signal::Winch,
+        Signal::Winch,
+        Signal::Usr2,
+        Signal::Usr1,
+        Signal::Usr1,
+    ] {
+        signals.prime(sig);
+    }
+    // Waits for the dispatch thread to get through everything.
+    signals.close();
+    // The second SIGWINCH is coalesced, SIGUSR2 ignored and the second
+    // SIGUSR1 doesn't fit; both of the dropped ones count.
+    assert_eq!(receiver.drain_available(), [Signal::Winch, Signal::Usr1]);
+    assert_eq!(signals.dropped_count(), 2);
+    let observed: Vec<_> = observed.try_iter().collect();
+    assert_eq!(observed.len(), 5);
+    for (_, thread) in observed {
+        assert_eq!(thread.as_deref(), Some("builder-test"));
+    }
+}
+
+#[test]
+fn the_builder_refuses_bad_options() {
+    let _serial = serial();
+    assert_eq!(
+        SignalsBuilder::new().buffer_capacity(0).build().err(),
+        Some(SignalError::InvalidBufferCapacity(0))
+    );
+    assert_eq!(
+        SignalsBuilder::new().signals(&[Signal::Kill]).build().err(),
+        Some(SignalError::Uncatchable(Signal::Kill))
+    );
+}
+
+// This is the only test that may call `set_ctrl_c_handler`, which can only
+// be done once per process.
+#[test]
+fn the_ctrl_c_handler_runs_on_sigint() {
+    let _serial = serial();
+    let (ran, runs) = mpsc::channel();
+    signal_msg::set_ctrl_c_handler(move || {
+        // Handlers are free to set up signal handling of their own.
+        drop(Signals::with_signals(&[Signal::Usr2]).unwrap());
+        let _ = ran.send(());
+    })
+    .unwrap();
+    Signal::Int.raise().unwrap();
+    assert_eq!(runs.recv_timeout(TIMEOUT), Ok(()));
+    assert_eq!(
+        signal_msg::set_ctrl_c_handler(|| {}),
+        Err(SignalError::HandlerAlreadySet)
+    );
+}
+
+#[test]
+fn paused_signals_are_delivered_on_resume() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1, Signal::Term]).unwrap();
+    let receiver = signals.subscribe();
+    signals.pause();
+    Signal::Usr1.raise().unwrap();
+    // Terminating signals aren't held back.
+    Signal::Term.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Term)));
+    assert_eq!(receiver.try_recv(), Ok(None));
+    signals.resume();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr1)));
+}
+
+#[test]
+fn counts_tally_the_received_signals() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1, Signal::Usr2]).unwrap();
+    let receiver = signals.subscribe();
+    // Each is waited for before the next, so the kernel can't merge them.
+    for sig in [Signal::Usr1, Signal::Usr2, Signal::Usr1] {
+        sig.raise().unwrap();
+        assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(sig)));
+    }
+    let counts = signals.counts();
+    assert_eq!(counts.get(&Signal::Usr1), Some(&2));
+    assert_eq!(counts.get(&Signal::Usr2), Some(&1));
+    assert_eq!(counts.len(), 2);
+    signals.reset_counts();
+    assert!(signals.counts().is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_failed_install_is_reported_and_rolled_back() {
+    let _serial = serial();
+    // Past SIGRTMAX, so the kernel refuses it.
+    let bogus = 100;
+    assert_eq!(
+        Signals::with_raw_signals(&[libc::SIGUSR1, bogus]).err(),
+        Some(SignalError::HandlerInstallFailed {
+            signal: bogus,
+            errno: libc::EINVAL,
+        })
+    );
+    assert_eq!(disposition(Signal::Usr1), libc::SIG_DFL);
+}
+
+#[test]
+fn chaining_calls_the_previous_handler() {
+    static CALLED: AtomicBool = AtomicBool::new(false);
+    extern "C" fn previous(_: libc::c_int) {
+        CALLED.store(true, Ordering::SeqCst);
+    }
+
+    let _serial = serial();
+    let handler = previous as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe { libc::signal(libc::SIGUSR2, handler) };
+    let signals = Signals::with_signals(&[Signal::Usr2])
+        .unwrap()
+        .with_chaining(true);
+    let receiver = signals.subscribe();
+    Signal::Usr2.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr2)));
+    assert!(CALLED.load(Ordering::SeqCst));
+    drop(signals);
+    assert_eq!(disposition(Signal::Usr2), handler);
+    unsafe { libc::signal(libc::SIGUSR2, libc::SIG_DFL) };
+}
+
+#[test]
+fn scope_restores_dispositions_after_a_panic() {
+    let _serial = serial();
+    let result = std::panic::catch_unwind(|| {
+        Signals::scope(&[Signal::Usr1], |_| {
+            assert_ne!(disposition(Signal::Usr1), libc::SIG_DFL);
+            panic!("oops");
+        })
+    });
+    assert!(result.is_err());
+    assert_eq!(disposition(Signal::Usr1), libc::SIG_DFL);
+}
+
+#[test]
+fn instances_handling_the_same_signal_both_receive_it() {
+    let _serial = serial();
+    let first = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    let second = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    let (first_receiver, second_receiver) = (first.subscribe(), second.subscribe());
+    Signal::Usr1.raise().unwrap();
+    assert_eq!(first_receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr1)));
+    assert_eq!(
+        second_receiver.recv_timeout(TIMEOUT),
+        Ok(Some(Signal::Usr1))
+    );
+    // The handler stays until the last of them is gone.
+    drop(first);
+    assert_ne!(disposition(Signal::Usr1), libc::SIG_DFL);
+    drop(second);
+    assert_eq!(disposition(Signal::Usr1), libc::SIG_DFL);
+}
+
+#[test]
+fn the_handler_runs_on_the_alternate_stack() {
+    let _serial = serial();
+    let signals = SignalsBuilder::new()
+        .signals(&[Signal::Segv])
+        .alt_stack(libc::SIGSTKSZ * 4)
+        .build()
+        .unwrap();
+    let flags = unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        libc::sigaction(libc::SIGSEGV, std::ptr::null(), &mut action);
+        action.sa_flags
+    };
+    assert_ne!(flags & libc::SA_ONSTACK, 0);
+    let receiver = signals.subscribe();
+    // Sent rather than caused by a bad access, so the handler returning is
+    // the end of it.
+    Signal::Segv.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Segv)));
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn the_shutdown_future_resolves_on_sigterm() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1, Signal::Term]).unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+    let resolved = runtime.block_on(async {
+        let shutdown = signals.shutdown_signal();
+        Signal::Usr1.raise().unwrap();
+        Signal::Term.raise().unwrap();
+        tokio::time::timeout(TIMEOUT, shutdown).await
+    });
+    assert_eq!(resolved, Ok(Ok(Signal::Term)));
+}
+
+#[test]
+fn oneshot_signals_are_only_delivered_once() {
+    let _serial = serial();
+    // SIGWINCH is ignored by default, so the second one is harmless.
+    let signals = Signals::with_signals(&[Signal::Winch, Signal::Usr2]).unwrap();
+    signals.oneshot(Signal::Winch);
+    let receiver = signals.subscribe();
+    Signal::Winch.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Winch)));
+    Signal::Winch.raise().unwrap();
+    Signal::Usr2.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr2)));
+    assert_eq!(signals.handled(), [Signal::Usr2]);
+}
+
+#[test]
+fn the_fd_becomes_readable_when_signals_arrive() {
+    use std::os::unix::io::AsRawFd;
+
+    let _serial = serial();
+    let poll = |fd, timeout: Duration| {
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut pollfd, 1, timeout.as_millis() as libc::c_int) }
+    };
+    let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    let fd = signals.as_raw_fd();
+    assert_eq!(poll(fd, Duration::ZERO), 0);
+    Signal::Usr1.raise().unwrap();
+    assert_eq!(poll(fd, TIMEOUT), 1);
+    assert_eq!(signals.drain_ready(), [Signal::Usr1]);
+    assert_eq!(poll(fd, Duration::ZERO), 0);
+    // Nothing to read is not an error, just nothing.
+    assert_eq!(signals.drain_ready(), []);
+}
+
+#[test]
+fn wait_count_returns_after_the_nth_signal() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1, Signal::Usr2]).unwrap();
+    let raised = std::sync::atomic::AtomicUsize::new(0);
+    let done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        // Keeps raising until `wait_count` is done, as the ones raised
+        // before it subscribed don't count.
+        scope.spawn(|| {
+            while !done.load(Ordering::SeqCst) {
+                Signal::Usr2.raise().unwrap();
+                Signal::Usr1.raise().unwrap();
+                raised.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+        let counted = signals.wait_count(Signal::Usr1, 3);
+        done.store(true, Ordering::SeqCst);
+        assert_eq!(counted, Ok(()));
+    });
+    assert!(raised.load(Ordering::SeqCst) >= 3);
+}
+
+#[test]
+fn forwarded_signals_reach_the_child() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let _serial = serial();
+    let mut child = Command::new("sh")
+        .args([
+            "-c",
+            "trap 'echo got USR1; exit 0' USR1; echo ready; while :; do sleep 0.01; done",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    assert_eq!(lines.next().unwrap().unwrap(), "ready");
+    let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    signals.forward_to(&child, &[Signal::Usr1]);
+    Signal::Usr1.raise().unwrap();
+    assert_eq!(lines.next().unwrap().unwrap(), "got USR1");
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn repeated_signals_are_all_delivered() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    let receiver = signals.subscribe();
+    for _ in 0..2 {
+        Signal::Usr1.raise().unwrap();
+        assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr1)));
+    }
+    // The handler is still ours after both.
+    assert_ne!(disposition(Signal::Usr1), libc::SIG_DFL);
+}
+
+#[test]
+fn last_reports_the_latest_signal() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1, Signal::Usr2]).unwrap();
+    let receiver = signals.subscribe();
+    assert_eq!(signals.last(), None);
+    Signal::Usr1.raise().unwrap();
+    Signal::Usr2.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr1)));
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr2)));
+    assert_eq!(signals.last(), Some(Signal::Usr2));
+    // Reading it consumes nothing.
+    assert_eq!(signals.last(), Some(Signal::Usr2));
+}
+
+#[test]
+fn blocked_signals_show_up_as_pending() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    let receiver = signals.subscribe();
+    let guard = signals.mask(&[Signal::Usr1]);
+    assert!(!Signals::pending_signals().contains(&Signal::Usr1));
+    Signal::Usr1.raise().unwrap();
+    assert!(Signals::pending_signals().contains(&Signal::Usr1));
+    drop(guard);
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr1)));
+    assert!(!Signals::pending_signals().contains(&Signal::Usr1));
+}
+
+#[test]
+fn reconfiguring_swaps_the_handled_signals() {
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Int, Signal::Term]).unwrap();
+    assert_ne!(disposition(Signal::Term), libc::SIG_DFL);
+    assert_eq!(disposition(Signal::Usr1), libc::SIG_DFL);
+    signals.reconfigure(&[Signal::Int, Signal::Usr1]).unwrap();
+    assert_eq!(disposition(Signal::Term), libc::SIG_DFL);
+    assert_ne!(disposition(Signal::Usr1), libc::SIG_DFL);
+    assert_eq!(signals.handled(), [Signal::Int, Signal::Usr1]);
+    let receiver = signals.subscribe();
+    Signal::Usr1.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr1)));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn unrelated_signals_dont_end_a_wait() {
+    use std::sync::atomic::AtomicUsize;
+
+    static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn interrupt(_: libc::c_int) {
+        INTERRUPTS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let _serial = serial();
+    let signals = Signals::with_signals(&[Signal::Usr1]).unwrap();
+    let receiver = signals.subscribe();
+    let tid = worker_thread();
+    // SIGURG is ignored by default, and an ignored signal interrupts
+    // nothing, so it gets a handler of its own. Without SA_RESTART, the
+    // worker's blocking read fails with EINTR each time it's hit.
+    let previous = unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        let mut previous: libc::sigaction = std::mem::zeroed();
+        assert_eq!(libc::sigaction(libc::SIGURG, &action, &mut previous), 0);
+        previous
+    };
+    for _ in 0..10 {
+        unsafe { libc::syscall(libc::SYS_tgkill, libc::getpid(), tid, libc::SIGURG) };
+    }
+    while INTERRUPTS.load(Ordering::SeqCst) == 0 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(receiver.try_recv(), Ok(None));
+    Signal::Usr1.raise().unwrap();
+    assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(Some(Signal::Usr1)));
+    unsafe { libc::sigaction(libc::SIGURG, &previous, std::ptr::null_mut()) };
+}
+
+#[test]
+fn running_out_of_descriptors_is_an_error() {
+    // This test has to be the one to create the pipe signals go through.
+    if !run_alone("running_out_of_descriptors_is_an_error") {
+        return;
+    }
+    let limit = unsafe {
+        let mut limit: libc::rlimit = std::mem::zeroed();
+        assert_eq!(libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit), 0);
+        limit
+    };
+    let none = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: limit.rlim_max,
+    };
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &none) }, 0);
+    let failed = signal_msg::set_ctrl_c_handler(|| {});
+    assert_eq!(unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) }, 0);
+    assert_eq!(
+        failed,
+        Err(SignalError::NotifierFailed {
+            errno: libc::EMFILE
+        })
+    );
+    // Nothing was left half set up, so trying again works.
+    assert_eq!(signal_msg::set_ctrl_c_handler(|| {}), Ok(()));
+}
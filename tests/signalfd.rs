@@ -0,0 +1,93 @@
+// Tests for the signalfd backend. It only works for signals that every
+// thread blocks, so this runs without the test harness, which would have
+// started threads of its own by the time a test runs: like a program using
+// the backend has to, `main` handles the signals before anything else.
+#[cfg(target_os = "linux")]
+fn main() {
+    use signal_msg::{Signal, Signals};
+    use std::process::Command;
+    use std::thread;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    let signals = Signals::with_signals(&[Signal::Usr1, Signal::Usr2, Signal::Term]).unwrap();
+    let receiver = signals.subscribe_info();
+    let pid = std::process::id() as i32;
+
+    // No handler is installed: the signals are only blocked.
+    for sig in [libc::SIGUSR1, libc::SIGUSR2, libc::SIGTERM].iter() {
+        assert_eq!(disposition(*sig), libc::SIG_DFL);
+        assert!(blocked_by(&format!("/proc/self/task/{}", pid), *sig));
+    }
+    // The worker blocks every signal, even those nobody handles yet.
+    let worker = worker_thread();
+    assert!(blocked_by(&worker, libc::SIGUSR1));
+    assert!(blocked_by(&worker, libc::SIGHUP));
+
+    Signal::Usr1.raise().unwrap();
+    let info = receiver.recv_timeout(TIMEOUT).unwrap().unwrap();
+    assert_eq!(info.signal, Signal::Usr1);
+    assert_eq!(info.sender_pid, Some(pid));
+
+    // A thread started from now on blocks the signals too, so one sent
+    // while it's running still only reaches the signalfd.
+    thread::spawn(|| Signal::Usr2.raise().unwrap())
+        .join()
+        .unwrap();
+    let info = receiver.recv_timeout(TIMEOUT).unwrap().unwrap();
+    assert_eq!(info.signal, Signal::Usr2);
+
+    // So does a signal from another process, along with who sent it; if it
+    // got its default action instead, this process would be gone.
+    let mut kill = Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .spawn()
+        .unwrap();
+    let sender = kill.id() as i32;
+    let info = receiver.recv_timeout(TIMEOUT).unwrap().unwrap();
+    assert_eq!(info.signal, Signal::Term);
+    assert_eq!(info.sender_pid, Some(sender));
+    assert!(kill.wait().unwrap().success());
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {}
+
+// The handler currently installed for `sig`, as sigaction(2) reports it.
+#[cfg(target_os = "linux")]
+fn disposition(sig: i32) -> libc::sighandler_t {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        assert_eq!(libc::sigaction(sig, std::ptr::null(), &mut action), 0);
+        action.sa_sigaction
+    }
+}
+
+// Whether the thread whose /proc directory is `task` blocks `sig`.
+#[cfg(target_os = "linux")]
+fn blocked_by(task: &str, sig: i32) -> bool {
+    let status = std::fs::read_to_string(format!("{}/status", task)).unwrap();
+    let blocked = status
+        .lines()
+        .find_map(|line| line.strip_prefix("SigBlk:"))
+        .map(|mask| u64::from_str_radix(mask.trim(), 16).unwrap())
+        .unwrap();
+    blocked & (1 << (sig - 1)) != 0
+}
+
+// The /proc directory of the thread that reads the signalfd.
+#[cfg(target_os = "linux")]
+fn worker_thread() -> String {
+    // The worker names itself once it's running, which may take a moment.
+    loop {
+        let worker = std::fs::read_dir("/proc/self/task")
+            .unwrap()
+            .map(|task| task.unwrap().path())
+            .find(|task| std::fs::read_to_string(task.join("comm")).unwrap() == "signal-msg\n");
+        if let Some(worker) = worker {
+            return worker.to_str().unwrap().to_string();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+}
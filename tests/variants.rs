@@ -0,0 +1,38 @@
+// What `Signal::all_variants` is for, done the way a crate depending on this
+// one would: `Signal` is `#[non_exhaustive]`, so a match on it outside this
+// crate needs a wildcard arm, and running every variant through the match
+// is what catches one the match doesn't know about.
+use signal_msg::Signal;
+
+fn name(sig: Signal) -> &'static str {
+    match sig {
+        Signal::Hup => "hangup",
+        Signal::Int => "interrupt",
+        Signal::Quit => "quit",
+        Signal::Ill => "illegal instruction",
+        Signal::Abrt => "abort",
+        Signal::Fpe => "floating point exception",
+        Signal::Kill => "kill",
+        Signal::Segv => "segmentation fault",
+        Signal::Pipe => "broken pipe",
+        Signal::Alrm => "alarm",
+        Signal::Term => "terminate",
+        Signal::Usr1 => "user 1",
+        Signal::Usr2 => "user 2",
+        Signal::Chld => "child",
+        Signal::Winch => "window change",
+        Signal::Cont => "continue",
+        Signal::Tstp => "terminal stop",
+        Signal::Stop => "stop",
+        Signal::Realtime(_) => "real-time",
+        _ => panic!("no name for {}", sig),
+    }
+}
+
+#[test]
+fn every_variant_has_a_name() {
+    let names: Vec<&str> = Signal::all_variants().iter().map(|s| name(*s)).collect();
+    assert_eq!(names.len(), 19);
+    assert_eq!(names.first(), Some(&"hangup"));
+    assert_eq!(names.last(), Some(&"real-time"));
+}